@@ -1,44 +1,37 @@
-extern crate futures;
-extern crate tokio_core;
-extern crate tokio_io;
-extern crate tokio_serde_json;
-extern crate tokio_netstring as netstring;
-
-#[macro_use]
-extern crate serde_json;
-
-use futures::{Future, Sink};
-
-use tokio_core::reactor::Core;
-use tokio_core::net::TcpStream;
-
-use tokio_serde_json::WriteJson;
-
-pub fn main() {
-    let mut core = Core::new().unwrap();
-    let handle = core.handle();
-
-    // Bind a server socket
-    let socket = TcpStream::connect(
-        &"127.0.0.1:17653".parse().unwrap(),
-        &handle);
-
-    core.run(socket.and_then(|socket| {
-
-        // Delimit frames using a netstring
-        let length_delimited = netstring::FramedWrite::new(socket);
-
-        // Serialize frames with JSON
-        let serialized = WriteJson::new(length_delimited);
-
-        // Send the value
-        serialized.send(json!({
-          "name": "John Doe",
-          "age": 43,
-          "phones": [
-            "+44 1234567",
-            "+44 2345678"
-          ]
-        }))
-    })).unwrap();
+use futures::SinkExt;
+
+use std::io;
+
+use tokio::net::TcpStream;
+
+use bytes::Bytes;
+use serde_json::{json, Value};
+use tokio_serde::formats::SymmetricalJson;
+use tokio_serde::SymmetricallyFramed;
+
+use tokio_netstring as netstring;
+
+#[tokio::main]
+pub async fn main() {
+    // Connect to the server
+    let socket = TcpStream::connect("127.0.0.1:17653").await.unwrap();
+
+    // Delimit frames using a netstring
+    let length_delimited = netstring::FramedWrite::<_, Bytes>::new(socket)
+        .sink_map_err(io::Error::from);
+
+    // Serialize frames with JSON
+    let mut serialized = SymmetricallyFramed::new(
+        length_delimited,
+        SymmetricalJson::<Value>::default());
+
+    // Send the value
+    serialized.send(json!({
+      "name": "John Doe",
+      "age": 43,
+      "phones": [
+        "+44 1234567",
+        "+44 2345678"
+      ]
+    })).await.unwrap();
 }
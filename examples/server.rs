@@ -1,43 +1,46 @@
-extern crate futures;
-extern crate tokio_core;
-extern crate tokio_io;
-extern crate tokio_serde_json;
-extern crate serde_json;
-extern crate tokio_netstring as netstring;
+use futures::TryStreamExt;
 
-use futures::Stream;
+use std::io;
 
-use tokio_core::reactor::Core;
-use tokio_core::net::TcpListener;
+use tokio::net::TcpListener;
 
 use serde_json::Value;
-use tokio_serde_json::ReadJson;
+use tokio_serde::formats::SymmetricalJson;
+use tokio_serde::SymmetricallyFramed;
 
-pub fn main() {
-    let mut core = Core::new().unwrap();
-    let handle = core.handle();
+use tokio_netstring as netstring;
 
+#[tokio::main]
+pub async fn main() {
     // Bind a server socket
-    let listener = TcpListener::bind(
-        &"127.0.0.1:17653".parse().unwrap(),
-        &handle).unwrap();
+    let listener = TcpListener::bind("127.0.0.1:17653").await.unwrap();
 
     println!("listening on {:?}", listener.local_addr());
 
-    core.run(listener.incoming().for_each(|(socket, _)| {
+    loop {
+        let (socket, _) = listener.accept().await.unwrap();
+
         // Delimit frames using netstring
-        let length_delimited = netstring::FramedRead::new(socket);
+        let length_delimited = netstring::FramedRead::new(socket)
+            .map_err(io::Error::from);
 
         // Deserialize frames
-        let deserialized = ReadJson::<_, Value>::new(length_delimited)
-            .map_err(|e| println!("ERR: {:?}", e));
+        let mut deserialized = SymmetricallyFramed::new(
+            length_delimited,
+            SymmetricalJson::<Value>::default());
 
         // Spawn a task that prints all received messages to STDOUT
-        handle.spawn(deserialized.for_each(|msg| {
-            println!("GOT: {:?}", msg);
-            Ok(())
-        }));
-
-        Ok(())
-    })).unwrap();
+        tokio::spawn(async move {
+            loop {
+                match deserialized.try_next().await {
+                    Ok(Some(msg)) => println!("GOT: {:?}", msg),
+                    Ok(None) => break,
+                    Err(e) => {
+                        println!("ERR: {:?}", e);
+                        break;
+                    }
+                }
+            }
+        });
+    }
 }
@@ -0,0 +1,336 @@
+use crate::parser::LengthParser;
+use crate::{Builder, NetstringError, NETSTRING_TAIL, READ_CHUNK_LEN};
+
+use bytes::{Buf, BytesMut};
+use futures_core::Stream;
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio_util::io::poll_read_buf;
+
+use std::fmt;
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{ready, Context, Poll, Waker};
+
+/// Adapts a byte stream to a `Stream` yielding a [`Body`] reader per frame.
+///
+/// Unlike `FramedRead`, `BodyStream` never buffers a whole payload, making
+/// it suited to frames far larger than the memory one is willing to spend
+/// on them. The next frame only becomes available once the previous body
+/// has been read to the end or dropped, a dropped body being discarded as
+/// it arrives. It is configured through [`Builder::new_body_read`].
+///
+/// The `max_frame_length`, `length_field_offset`, `max_length_digits` and
+/// `lenient` settings apply, frames over the max frame length fail the
+/// stream with `NetstringError::FrameTooLarge`.
+///
+/// [`Builder::new_body_read`]: struct.Builder.html#method.new_body_read
+pub struct BodyStream<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+}
+
+/// The payload of a single frame, yielded by [`BodyStream`].
+///
+/// `Body` implements `AsyncRead`, reading exactly the payload length
+/// declared by the frame head. The trailing `','` is checked once the
+/// payload has been read, before reporting EOF.
+pub struct Body<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+
+    // Declared payload length
+    len: u64,
+
+    // EOF was reported, or an error
+    done: bool,
+}
+
+// State shared by the stream and the body being read
+struct Shared<T> {
+    // I/O type
+    inner: Pin<Box<T>>,
+
+    // Configuration values
+    builder: Builder,
+
+    // Bytes read from `inner` and not consumed yet
+    buffer: BytesMut,
+
+    // Read state
+    state: BodyState,
+
+    // The stream waiting for the current body to be done with
+    waker: Option<Waker>,
+}
+
+enum BodyState {
+    // Scanning the next frame head
+    Head(LengthParser),
+    // A body is out, `remaining` payload bytes are still to come. Once it
+    // is `dropped`, the stream discards them.
+    Body { remaining: u64, dropped: bool },
+    // The stream ended or failed
+    Done,
+}
+
+// ===== impl BodyStream =====
+
+impl<T> BodyStream<T> {
+    pub(crate) fn new(inner: T, builder: Builder) -> BodyStream<T> {
+        BodyStream {
+            shared: Arc::new(Mutex::new(Shared {
+                inner: Box::pin(inner),
+                builder,
+                buffer: BytesMut::new(),
+                state: BodyState::Head(LengthParser::default()),
+                waker: None,
+            })),
+        }
+    }
+}
+
+impl<T: AsyncRead> Stream for BodyStream<T> {
+    type Item = Result<Body<T>, NetstringError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut shared = self.shared.lock().unwrap();
+
+        loop {
+            match shared.state {
+                BodyState::Done => return Poll::Ready(None),
+                BodyState::Body { dropped: false, .. } => {
+                    // Wait for the current body to be read or dropped
+                    shared.waker = Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+                BodyState::Body { dropped: true, .. } => {
+                    if let Err(e) = ready!(shared.poll_discard(cx)) {
+                        shared.state = BodyState::Done;
+                        return Poll::Ready(Some(Err(e)));
+                    }
+                }
+                BodyState::Head(mut length) => {
+                    match shared.decode_head(&mut length) {
+                        Ok(Some(len)) => {
+                            shared.state = BodyState::Body { remaining: len, dropped: false };
+
+                            return Poll::Ready(Some(Ok(Body {
+                                shared: self.shared.clone(),
+                                len,
+                                done: false,
+                            })));
+                        }
+                        Ok(None) => shared.state = BodyState::Head(length),
+                        Err(e) => {
+                            shared.state = BodyState::Done;
+                            return Poll::Ready(Some(Err(e)));
+                        }
+                    }
+
+                    // Read more of the head
+                    let res = ready!(shared.poll_fill(cx, None));
+
+                    if let Err(e) = res {
+                        shared.state = BodyState::Done;
+
+                        return match e {
+                            // The stream ended between two frames
+                            NetstringError::UnexpectedEof { buffered: 0, .. } => Poll::Ready(None),
+                            e => Poll::Ready(Some(Err(e))),
+                        };
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<T> fmt::Debug for BodyStream<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BodyStream").finish()
+    }
+}
+
+// ===== impl Body =====
+
+impl<T> Body<T> {
+    /// Returns the payload length declared by the frame head.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if the frame has an empty payload.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<T: AsyncRead> AsyncRead for Body<T> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        if this.done || buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        let mut shared = this.shared.lock().unwrap();
+
+        let remaining = match shared.state {
+            BodyState::Body { remaining, .. } => remaining,
+            _ => unreachable!(),
+        };
+
+        if remaining > 0 {
+            if shared.buffer.is_empty() {
+                if let Err(e) = ready!(shared.poll_fill(cx, Some(remaining))) {
+                    this.done = true;
+                    return Poll::Ready(shared.end_body(Err(e)));
+                }
+            }
+
+            let n = remaining.min(shared.buffer.len() as u64) as usize;
+            let n = n.min(buf.remaining());
+
+            buf.put_slice(&shared.buffer[..n]);
+            shared.buffer.advance(n);
+            shared.state = BodyState::Body { remaining: remaining - n as u64, dropped: false };
+
+            return Poll::Ready(Ok(()));
+        }
+
+        // The payload has been read, check the ',' before reporting EOF
+        let res = ready!(shared.poll_tail(cx));
+
+        this.done = true;
+        Poll::Ready(shared.end_body(res))
+    }
+}
+
+impl<T> Drop for Body<T> {
+    fn drop(&mut self) {
+        if self.done {
+            return;
+        }
+
+        // Hand the rest of the payload over to the stream to discard
+        let mut shared = match self.shared.lock() {
+            Ok(shared) => shared,
+            Err(_) => return,
+        };
+
+        if let BodyState::Body { ref mut dropped, .. } = shared.state {
+            *dropped = true;
+        }
+
+        if let Some(waker) = shared.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> fmt::Debug for Body<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Body")
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+// ===== impl Shared =====
+
+impl<T: AsyncRead> Shared<T> {
+    // Parse the frame head at the start of the buffer, consuming it
+    fn decode_head(&mut self, length: &mut LengthParser) -> Result<Option<u64>, NetstringError> {
+        let offset = self.builder.length_field_offset;
+
+        if self.buffer.len() <= offset {
+            // Not enough data
+            return Ok(None);
+        }
+
+        let (len, i) = match length.parse(&self.builder, &self.buffer[offset..])? {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+
+        if len > self.builder.max_frame_len as u64 {
+            return Err(NetstringError::FrameTooLarge { declared: len, max: self.builder.max_frame_len });
+        }
+
+        self.buffer.advance(offset + i + 1);
+
+        Ok(Some(len))
+    }
+
+    // Read more bytes into the buffer. `expected` is the number of bytes
+    // the frame still needs, reported if the stream ends.
+    fn poll_fill(&mut self, cx: &mut Context<'_>, expected: Option<u64>) -> Poll<Result<(), NetstringError>> {
+        if self.buffer.len() == self.buffer.capacity() {
+            self.buffer.reserve(READ_CHUNK_LEN);
+        }
+
+        if ready!(poll_read_buf(self.inner.as_mut(), cx, &mut self.buffer))? == 0 {
+            return Poll::Ready(Err(NetstringError::UnexpectedEof {
+                // Note: there is a ',' after the payload
+                expected: expected.map(|n| usize::try_from(n.saturating_add(1)).unwrap_or(usize::MAX)),
+                buffered: self.buffer.len(),
+            }));
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    // Check the ',' following the payload
+    fn poll_tail(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), NetstringError>> {
+        if self.buffer.is_empty() {
+            ready!(self.poll_fill(cx, Some(0)))?;
+        }
+
+        if !self.builder.terminators.contains(&self.buffer[0]) {
+            return Poll::Ready(Err(NetstringError::MissingTerminator { found: self.buffer[0], expected: self.builder.terminators }));
+        }
+
+        self.buffer.advance(NETSTRING_TAIL.len());
+
+        Poll::Ready(Ok(()))
+    }
+
+    // Let the stream move on once the body is done with, successfully or not
+    fn end_body(&mut self, res: Result<(), NetstringError>) -> io::Result<()> {
+        self.state = match res {
+            Ok(()) => BodyState::Head(LengthParser::default()),
+            Err(_) => BodyState::Done,
+        };
+
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+
+        res.map_err(io::Error::from)
+    }
+
+    // Discard the rest of a dropped body
+    fn poll_discard(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), NetstringError>> {
+        loop {
+            let remaining = match self.state {
+                BodyState::Body { remaining, .. } => remaining,
+                _ => unreachable!(),
+            };
+
+            if remaining == 0 {
+                ready!(self.poll_tail(cx))?;
+                self.state = BodyState::Head(LengthParser::default());
+
+                return Poll::Ready(Ok(()));
+            }
+
+            if self.buffer.is_empty() {
+                ready!(self.poll_fill(cx, Some(remaining)))?;
+            }
+
+            let n = remaining.min(self.buffer.len() as u64) as usize;
+
+            self.buffer.advance(n);
+            self.state = BodyState::Body { remaining: remaining - n as u64, dropped: true };
+        }
+    }
+}
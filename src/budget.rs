@@ -0,0 +1,219 @@
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{ready, Context, Poll, Waker};
+
+use bytes::BytesMut;
+
+/// A memory budget shared by many framers.
+///
+/// A `MemoryBudget` caps the total number of bytes held in the read buffers
+/// of `FramedRead` values and the write queues of `FramedWrite` values that
+/// were built from a [`Builder`] it is attached to, through
+/// [`Builder::memory_budget`]. Cloning a `MemoryBudget` returns a new handle
+/// to the same budget.
+///
+/// Once the budget is exhausted, `FramedRead` stops reading from its
+/// transport and `FramedWrite` stops accepting frames until memory is
+/// released by another framer. The budget should therefore leave room for
+/// at least one frame in flight per connection, or connections waiting on
+/// each other may stall. A frame too large to ever fit in the budget fails
+/// the `FramedRead` with `NetstringError::FrameTooLarge`, as soon as its
+/// head is read. A frame sent that does not fit in what is left of the
+/// budget is queued outside of it, and written out before `FramedWrite`
+/// accepts another frame.
+///
+/// Only bytes held by the framers count against the budget. A decoded
+/// frame is split off the read buffer and released from the budget as it
+/// is yielded, even though its memory lives on until the frame is dropped.
+/// Likewise, frames written to the transport are released from the write
+/// queue.
+///
+/// [`Builder`]: struct.Builder.html
+/// [`Builder::memory_budget`]: struct.Builder.html#method.memory_budget
+#[derive(Clone)]
+pub struct MemoryBudget {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    // Maximum number of bytes
+    limit: usize,
+
+    // Number of bytes currently reserved
+    used: AtomicUsize,
+
+    // Tasks waiting for memory to be released
+    waiters: Mutex<Vec<Waker>>,
+}
+
+// Bytes reserved from an optional budget by a single buffer, released on drop
+#[derive(Debug, Default)]
+pub(crate) struct Charge {
+    budget: Option<MemoryBudget>,
+    amount: usize,
+}
+
+// ===== impl MemoryBudget =====
+
+impl MemoryBudget {
+    /// Creates a new `MemoryBudget` of `limit` bytes.
+    pub fn new(limit: usize) -> MemoryBudget {
+        MemoryBudget {
+            inner: Arc::new(Inner {
+                limit,
+                used: AtomicUsize::new(0),
+                waiters: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// Returns the number of bytes in the budget.
+    pub fn limit(&self) -> usize {
+        self.inner.limit
+    }
+
+    /// Returns the number of bytes currently reserved.
+    pub fn used(&self) -> usize {
+        self.inner.used.load(Ordering::Acquire)
+    }
+
+    /// Returns the number of bytes that can still be reserved.
+    pub fn available(&self) -> usize {
+        self.limit().saturating_sub(self.used())
+    }
+
+    // Wait until some of the budget is available
+    pub(crate) fn poll_available(&self, cx: &mut Context<'_>) -> Poll<()> {
+        if self.available() > 0 {
+            return Poll::Ready(());
+        }
+
+        let mut waiters = self.inner.waiters.lock().unwrap();
+
+        // Check again now that a release cannot slip through unnoticed
+        if self.available() > 0 {
+            return Poll::Ready(());
+        }
+
+        if !waiters.iter().any(|w| w.will_wake(cx.waker())) {
+            waiters.push(cx.waker().clone());
+        }
+
+        Poll::Pending
+    }
+
+    // Reserve `n` bytes, unless that would go over the limit
+    fn try_acquire(&self, n: usize) -> bool {
+        let limit = self.limit();
+
+        self.inner.used
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |used| {
+                used.checked_add(n).filter(|&used| used <= limit)
+            })
+            .is_ok()
+    }
+
+    fn release(&self, n: usize) {
+        self.inner.used.fetch_sub(n, Ordering::AcqRel);
+
+        if self.available() > 0 {
+            let waiters = std::mem::take(&mut *self.inner.waiters.lock().unwrap());
+
+            for waker in waiters {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl fmt::Debug for MemoryBudget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MemoryBudget")
+            .field("limit", &self.limit())
+            .field("used", &self.used())
+            .finish()
+    }
+}
+
+// ===== impl Charge =====
+
+impl Charge {
+    pub(crate) fn new(budget: Option<MemoryBudget>) -> Charge {
+        Charge { budget, amount: 0 }
+    }
+
+    pub(crate) fn budget(&self) -> Option<&MemoryBudget> {
+        self.budget.as_ref()
+    }
+
+    // Hold `n` more bytes against the budget, if it has room for them.
+    // Always succeeds without a budget.
+    pub(crate) fn try_acquire(&mut self, n: usize) -> bool {
+        if let Some(ref budget) = self.budget {
+            if !budget.try_acquire(n) {
+                return false;
+            }
+
+            self.amount += n;
+        }
+
+        true
+    }
+
+    // Release `n` of the bytes held against the budget
+    pub(crate) fn release(&mut self, n: usize) {
+        if let Some(ref budget) = self.budget {
+            debug_assert!(n <= self.amount, "released more than was held");
+            budget.release(n);
+            self.amount -= n;
+        }
+    }
+
+    // Release the bytes held over `amount`
+    pub(crate) fn release_to(&mut self, amount: usize) {
+        self.release(self.amount.saturating_sub(amount));
+    }
+
+    // Grow `buf` to fit up to `additional` more bytes. The new capacity is
+    // acquired from the budget before it is allocated, waiting for memory to
+    // be released when there is none left, so the buffer never outgrows the
+    // budget.
+    pub(crate) fn poll_reserve(&mut self, cx: &mut Context<'_>, buf: &mut BytesMut, additional: usize) -> Poll<()> {
+        let budget = match self.budget {
+            Some(ref budget) => budget.clone(),
+            None => {
+                buf.reserve(additional);
+                return Poll::Ready(());
+            }
+        };
+
+        loop {
+            ready!(budget.poll_available(cx));
+
+            let capacity = buf.capacity();
+            let available = budget.available();
+
+            // Double the capacity as `BytesMut::reserve` would, within the
+            // part of the budget still available
+            let wanted = buf.len() + additional.min(available);
+            let grow = wanted.max(capacity.saturating_mul(2)).min(capacity + available) - capacity;
+
+            // Another framer may have taken the memory in the meantime
+            if grow == 0 || !self.try_acquire(grow) {
+                continue;
+            }
+
+            let mut grown = BytesMut::with_capacity(capacity + grow);
+            grown.extend_from_slice(buf);
+            *buf = grown;
+
+            return Poll::Ready(());
+        }
+    }
+}
+impl Drop for Charge {
+    fn drop(&mut self) {
+        self.release_to(0);
+    }
+}
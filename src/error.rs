@@ -0,0 +1,198 @@
+use alloc::vec::Vec;
+
+use core::fmt;
+
+#[cfg(feature = "std")]
+use std::error::Error;
+#[cfg(feature = "std")]
+use std::io;
+
+/// An error produced while encoding or decoding netstrings.
+///
+/// With the `std` feature, `NetstringError` converts to and from
+/// `io::Error`, so it can be propagated with `?` from functions returning
+/// `io::Result`. Converting an `io::Error` that wraps a `NetstringError`
+/// back yields the original value.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum NetstringError {
+    /// The length field is not a valid netstring length.
+    ///
+    /// `bytes` holds the length field up to and including the offending
+    /// byte, found at `offset` within the field.
+    LengthParse {
+        /// Position of the offending byte within the length field
+        offset: usize,
+        /// The length field bytes scanned so far
+        bytes: Vec<u8>,
+    },
+
+    /// The frame is larger than the configured max frame length.
+    FrameTooLarge {
+        /// The length declared by the frame, or submitted for encoding
+        declared: u64,
+        /// The configured max frame length
+        max: usize,
+    },
+
+    /// The byte following the payload is not the `','` terminator, or for
+    /// tnetstrings, not a type tag.
+    MissingTerminator {
+        /// The byte found in place of the terminator
+        found: u8,
+        /// The bytes accepted as a terminator
+        expected: &'static [u8],
+    },
+
+    /// The stream ended in the middle of a frame.
+    UnexpectedEof {
+        /// Number of bytes the frame still needed, if its length was known
+        expected: Option<usize>,
+        /// Number of bytes buffered when the stream ended
+        buffered: usize,
+    },
+
+    /// Corrupt bytes were discarded, in resync mode.
+    ///
+    /// Decoding carries on with the frame following them.
+    Corrupt {
+        /// Position of the first byte discarded in the stream
+        offset: u64,
+        /// Number of bytes discarded
+        skipped: u64,
+    },
+
+    /// The source of a frame body ended before the declared length.
+    BodyTooShort {
+        /// The payload length written in the frame head
+        declared: u64,
+        /// Number of bytes the source held
+        read: u64,
+    },
+
+    /// The source of a frame body holds more than the declared length.
+    BodyTooLong {
+        /// The payload length written in the frame head
+        declared: u64,
+    },
+
+    /// The prefix of a frame to encode does not span the length field offset.
+    PrefixLength {
+        /// Number of bytes in the prefix
+        len: usize,
+        /// The configured length field offset
+        expected: usize,
+    },
+
+    /// The payload of a tnetstring is not a valid value for its type tag.
+    InvalidValue {
+        /// The type tag of the tnetstring
+        tag: u8,
+    },
+
+    /// Lists and dictionaries of a tnetstring are nested too deep.
+    NestingTooDeep {
+        /// The maximum number of nested lists and dictionaries
+        max: usize,
+    },
+
+    /// An I/O error from the underlying stream.
+    #[cfg(feature = "std")]
+    Io(io::Error),
+}
+
+#[cfg(feature = "std")]
+impl NetstringError {
+    // The `io::ErrorKind` used when converting into an `io::Error`
+    fn kind(&self) -> io::ErrorKind {
+        match *self {
+            NetstringError::UnexpectedEof { .. } => io::ErrorKind::UnexpectedEof,
+            NetstringError::BodyTooShort { .. } |
+            NetstringError::BodyTooLong { .. } |
+            NetstringError::PrefixLength { .. } => io::ErrorKind::InvalidInput,
+            NetstringError::Io(ref e) => e.kind(),
+            _ => io::ErrorKind::InvalidData,
+        }
+    }
+}
+
+impl fmt::Display for NetstringError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            NetstringError::LengthParse { offset, ref bytes } => {
+                write!(f, "invalid length `{}` at byte {}", bytes.escape_ascii(), offset)
+            }
+            NetstringError::FrameTooLarge { declared, max } => {
+                write!(f, "frame of {} bytes exceeds the max frame length of {}", declared, max)
+            }
+            NetstringError::MissingTerminator { found, expected: &[expected] } => {
+                write!(f, "missing terminator: expected `{}`, found `{}`",
+                       expected.escape_ascii(), found.escape_ascii())
+            }
+            NetstringError::MissingTerminator { found, expected } => {
+                write!(f, "missing terminator: expected one of `{}`, found `{}`",
+                       expected.escape_ascii(), found.escape_ascii())
+            }
+            NetstringError::UnexpectedEof { expected: Some(expected), buffered } => {
+                write!(f, "stream ended {} bytes short of the end of the frame ({} bytes buffered)",
+                       expected, buffered)
+            }
+            NetstringError::UnexpectedEof { expected: None, buffered } => {
+                write!(f, "stream ended in the middle of a frame head ({} bytes buffered)", buffered)
+            }
+            NetstringError::Corrupt { offset, skipped } => {
+                write!(f, "skipped {} corrupt bytes at offset {}", skipped, offset)
+            }
+            NetstringError::BodyTooShort { declared, read } => {
+                write!(f, "body source ended after {} of the {} declared bytes", read, declared)
+            }
+            NetstringError::BodyTooLong { declared } => {
+                write!(f, "body source holds more than the {} declared bytes", declared)
+            }
+            NetstringError::PrefixLength { len, expected } => {
+                write!(f, "frame prefix of {} bytes does not match the length field offset of {}", len, expected)
+            }
+            NetstringError::InvalidValue { tag } => {
+                write!(f, "invalid payload for a tnetstring of type `{}`", tag.escape_ascii())
+            }
+            NetstringError::NestingTooDeep { max } => {
+                write!(f, "tnetstring nests more than {} lists and dictionaries", max)
+            }
+            #[cfg(feature = "std")]
+            NetstringError::Io(ref e) => e.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for NetstringError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            NetstringError::Io(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for NetstringError {
+    fn from(e: io::Error) -> NetstringError {
+        if e.get_ref().is_some_and(|inner| inner.is::<NetstringError>()) {
+            // Unwrap a `NetstringError` that went through an `io::Error`
+            let inner = e.into_inner().unwrap();
+            return *inner.downcast::<NetstringError>().unwrap();
+        }
+
+        NetstringError::Io(e)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<NetstringError> for io::Error {
+    fn from(e: NetstringError) -> io::Error {
+        match e {
+            NetstringError::Io(e) => e,
+            e => io::Error::new(e.kind(), e),
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use crate::{Frame, NetstringDecoder, NetstringError, RawEvent};
+
+use bytes::{Bytes, BytesMut};
+use tokio_util::codec;
+
+/// A decoded frame, or the outcome of a frame over the max frame length.
+///
+/// `Event` is yielded by [`EventDecoder`] and by the `FramedRead` returned
+/// from [`Builder::new_event_read`]. Which events are yielded for an
+/// oversized frame depends on [`Builder::on_oversize`].
+///
+/// [`Builder::new_event_read`]: struct.Builder.html#method.new_event_read
+/// [`Builder::on_oversize`]: struct.Builder.html#method.on_oversize
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// A frame within the max frame length.
+    Frame(Frame),
+
+    /// An oversized frame was discarded, with `OversizePolicy::Skip`.
+    Skipped {
+        /// Position of the first byte of the frame in the stream
+        offset: u64,
+        /// The payload length declared by the frame head
+        len: u64,
+    },
+
+    /// A piece of the payload of an oversized frame, with
+    /// `OversizePolicy::Stream`.
+    ///
+    /// The chunks of a frame share its `offset` and `len`. The last one is
+    /// only yielded once the trailing `','` has been checked, its `data` may
+    /// be empty.
+    Chunk {
+        /// Position of the first byte of the frame in the stream
+        offset: u64,
+        /// The payload length declared by the frame head
+        len: u64,
+        /// The payload bytes
+        data: Bytes,
+        /// This is the end of the payload
+        last: bool,
+    },
+}
+
+/// Decodes netstring frames, reporting oversized frames as they go.
+///
+/// `EventDecoder` implements the `tokio-util` [`Decoder`] trait, yielding
+/// [`Event`] values. It is configured through [`Builder::new_event_decoder`].
+/// Like [`FrameDecoder`], the `strip_frame` setting does not apply.
+///
+/// [`Decoder`]: https://docs.rs/tokio-util/0.7/tokio_util/codec/trait.Decoder.html
+/// [`Builder::new_event_decoder`]: struct.Builder.html#method.new_event_decoder
+/// [`FrameDecoder`]: struct.FrameDecoder.html
+#[derive(Debug)]
+pub struct EventDecoder {
+    inner: NetstringDecoder,
+}
+
+// ===== impl EventDecoder =====
+
+impl EventDecoder {
+    /// Creates a new `EventDecoder` with default configuration values.
+    pub fn new() -> EventDecoder {
+        crate::Builder::new().new_event_decoder()
+    }
+
+    pub(crate) fn from_decoder(mut inner: NetstringDecoder) -> EventDecoder {
+        inner.events = true;
+        EventDecoder { inner }
+    }
+}
+
+impl Default for EventDecoder {
+    fn default() -> EventDecoder {
+        EventDecoder::new()
+    }
+}
+
+impl codec::Decoder for EventDecoder {
+    type Item = Event;
+    type Error = NetstringError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Event>, NetstringError> {
+        let event = match self.inner.decode_event(src)? {
+            Some(event) => event,
+            None => return Ok(None),
+        };
+
+        Ok(Some(match event {
+            RawEvent::Frame(frame) => {
+                Event::Frame(Frame::from_raw(frame, self.inner.builder.length_field_offset))
+            }
+            RawEvent::Skipped { offset, len } => Event::Skipped { offset, len },
+            RawEvent::Chunk { offset, len, data, last } => {
+                Event::Chunk { offset, len, data: data.freeze(), last }
+            }
+        }))
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Event>, NetstringError> {
+        match self.decode(src)? {
+            Some(event) => Ok(Some(event)),
+            None if !self.inner.in_frame(src) => Ok(None),
+            None => Err(self.inner.eof_error(src)),
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use crate::{NetstringDecoder, NetstringError, RawFrame};
+
+use bytes::{Bytes, BytesMut};
+use tokio_util::codec;
+
+/// A decoded frame along with its metadata.
+///
+/// `Frame` is yielded by [`FrameDecoder`] and by the `FramedRead` returned
+/// from [`Builder::new_frame_read`]. On top of the payload, it carries the
+/// bytes skipped before the length field, as set by
+/// [`Builder::length_field_offset`], and the position of the frame in the
+/// stream.
+///
+/// [`Builder::new_frame_read`]: struct.Builder.html#method.new_frame_read
+/// [`Builder::length_field_offset`]: struct.Builder.html#method.length_field_offset
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    prefix: Bytes,
+    payload: Bytes,
+    offset: u64,
+}
+
+/// A payload to encode with a prefix of its own.
+///
+/// `NetstringEncoder` and `NetstringCodec` encode `Prefixed` values on top
+/// of plain payloads, writing `prefix` in place of the one set by
+/// [`Builder::write_prefix`]. The prefix must be `length_field_offset` bytes
+/// long, encoding fails with `NetstringError::PrefixLength` otherwise.
+///
+/// [`Builder::write_prefix`]: struct.Builder.html#method.write_prefix
+///
+/// # Examples
+///
+/// ```
+/// use tokio_netstring::{Builder, Prefixed};
+/// use tokio_util::codec::Encoder;
+/// use bytes::BytesMut;
+///
+/// let mut encoder = Builder::new()
+///     .length_field_offset(1)
+///     .new_encoder();
+///
+/// let mut dst = BytesMut::new();
+/// encoder.encode(Prefixed::new(&b"A"[..], &b"hi"[..]), &mut dst).unwrap();
+/// encoder.encode(Prefixed::new(&b"B"[..], &b"hey"[..]), &mut dst).unwrap();
+/// assert_eq!(dst, &b"A2:hi,B3:hey,"[..]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Prefixed<B> {
+    prefix: Bytes,
+    payload: B,
+}
+
+/// Decodes netstring frames along with their metadata.
+///
+/// `FrameDecoder` implements the `tokio-util` [`Decoder`] trait, yielding
+/// [`Frame`] values. It is configured through [`Builder::new_frame_decoder`].
+/// The `strip_frame` setting does not apply, the prefix and payload are
+/// always available separately.
+///
+/// [`Decoder`]: https://docs.rs/tokio-util/0.7/tokio_util/codec/trait.Decoder.html
+/// [`Builder::new_frame_decoder`]: struct.Builder.html#method.new_frame_decoder
+#[derive(Debug)]
+pub struct FrameDecoder {
+    inner: NetstringDecoder,
+}
+
+// ===== impl Frame =====
+
+impl Frame {
+    /// Returns the bytes preceding the length field.
+    ///
+    /// These are the `length_field_offset` bytes at the start of the frame,
+    /// typically a version or type tag.
+    pub fn prefix(&self) -> &Bytes {
+        &self.prefix
+    }
+
+    /// Returns the frame payload.
+    pub fn payload(&self) -> &Bytes {
+        &self.payload
+    }
+
+    /// Consumes the `Frame`, returning its payload.
+    pub fn into_payload(self) -> Bytes {
+        self.payload
+    }
+
+    /// Returns the payload length declared by the frame head.
+    pub fn len(&self) -> usize {
+        self.payload.len()
+    }
+
+    /// Returns `true` if the frame has an empty payload.
+    pub fn is_empty(&self) -> bool {
+        self.payload.is_empty()
+    }
+
+    /// Returns the position of the first byte of the frame in the stream.
+    ///
+    /// The offset counts every byte decoded since the decoder was created,
+    /// starting at `0`, and points at the first prefix byte.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    // Split a raw frame into its prefix of `prefix_len` bytes and payload
+    pub(crate) fn from_raw(frame: RawFrame, prefix_len: usize) -> Frame {
+        let buf = frame.buf.freeze();
+
+        Frame {
+            prefix: buf.slice(..prefix_len),
+            payload: buf.slice(frame.head..frame.head + frame.len),
+            offset: frame.offset,
+        }
+    }
+}
+
+// ===== impl Prefixed =====
+
+impl<B> Prefixed<B> {
+    /// Creates a new `Prefixed` writing `prefix` before the frame carrying
+    /// `payload`.
+    pub fn new(prefix: impl Into<Bytes>, payload: B) -> Prefixed<B> {
+        Prefixed {
+            prefix: prefix.into(),
+            payload,
+        }
+    }
+
+    /// Returns the bytes written before the length field.
+    pub fn prefix(&self) -> &Bytes {
+        &self.prefix
+    }
+
+    /// Returns the payload.
+    pub fn payload(&self) -> &B {
+        &self.payload
+    }
+
+    /// Consumes the `Prefixed`, returning its prefix and payload.
+    pub fn into_parts(self) -> (Bytes, B) {
+        (self.prefix, self.payload)
+    }
+}
+
+// ===== impl FrameDecoder =====
+
+impl FrameDecoder {
+    /// Creates a new `FrameDecoder` with default configuration values.
+    pub fn new() -> FrameDecoder {
+        crate::Builder::new().new_frame_decoder()
+    }
+
+    pub(crate) fn from_decoder(inner: NetstringDecoder) -> FrameDecoder {
+        FrameDecoder { inner }
+    }
+}
+
+impl Default for FrameDecoder {
+    fn default() -> FrameDecoder {
+        FrameDecoder::new()
+    }
+}
+
+impl codec::Decoder for FrameDecoder {
+    type Item = Frame;
+    type Error = NetstringError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Frame>, NetstringError> {
+        match self.inner.decode_frame(src)? {
+            Some(frame) => Ok(Some(Frame::from_raw(frame, self.inner.builder.length_field_offset))),
+            None => Ok(None),
+        }
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Frame>, NetstringError> {
+        match self.decode(src)? {
+            Some(frame) => Ok(Some(frame)),
+            None if !self.inner.in_frame(src) => Ok(None),
+            None => Err(self.inner.eof_error(src)),
+        }
+    }
+}
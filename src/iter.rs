@@ -0,0 +1,237 @@
+use crate::parser::ParseState;
+use crate::{Builder, NetstringError, OversizePolicy, Status, NETSTRING_TAIL};
+
+use bytes::Bytes;
+
+use core::iter::FusedIterator;
+use core::ops::Range;
+
+/// An iterator over the frames of an in-memory buffer.
+///
+/// `ParseIter` yields the payloads as slices of the buffer, without copying
+/// them. It is created by [`parse_iter`] or [`Builder::new_parse_iter`].
+///
+/// Frames are validated like [`NetstringDecoder`] does, with the same
+/// configuration settings. Iteration ends after the first error, unless it
+/// is a `NetstringError::Corrupt` reported in resync mode. Bytes left at the
+/// end of the buffer that do not form a complete frame are reported as
+/// `NetstringError::UnexpectedEof`. Oversized frames are skipped with
+/// `OversizePolicy::Skip`, and reported as `NetstringError::FrameTooLarge`
+/// with `OversizePolicy::Stream`.
+///
+/// [`parse_iter`]: fn.parse_iter.html
+/// [`Builder::new_parse_iter`]: struct.Builder.html#method.new_parse_iter
+/// [`NetstringDecoder`]: struct.NetstringDecoder.html
+#[derive(Debug, Clone)]
+pub struct ParseIter<'a> {
+    buf: &'a [u8],
+    cursor: Cursor,
+}
+
+/// An iterator over the frames of a `Bytes` buffer.
+///
+/// `ParseIterBytes` yields the payloads as `Bytes` values sharing the
+/// memory of the buffer, without copying them. It is created by
+/// [`parse_iter_bytes`] or [`Builder::new_parse_iter_bytes`], and follows
+/// the same rules as [`ParseIter`].
+///
+/// [`parse_iter_bytes`]: fn.parse_iter_bytes.html
+/// [`Builder::new_parse_iter_bytes`]: struct.Builder.html#method.new_parse_iter_bytes
+/// [`ParseIter`]: struct.ParseIter.html
+#[derive(Debug, Clone)]
+pub struct ParseIterBytes {
+    buf: Bytes,
+    cursor: Cursor,
+}
+
+// Position of the iterators within their buffer
+#[derive(Debug, Clone)]
+struct Cursor {
+    // Configuration values
+    builder: Builder,
+
+    // Position of the next frame
+    pos: usize,
+
+    // No more frames will be yielded, after the end or an error
+    done: bool,
+}
+
+/// Iterates over the frames of `buf`, with default configuration values.
+///
+/// # Examples
+///
+/// ```
+/// let frames = tokio_netstring::parse_iter(b"5:hello,5:world,")
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+///
+/// assert_eq!(frames, [&b"hello"[..], &b"world"[..]]);
+/// ```
+pub fn parse_iter(buf: &[u8]) -> ParseIter<'_> {
+    Builder::new().new_parse_iter(buf)
+}
+
+/// Iterates over the frames of `buf` as shared `Bytes` slices, with default
+/// configuration values.
+///
+/// # Examples
+///
+/// ```
+/// use bytes::Bytes;
+///
+/// let buf = Bytes::from_static(b"5:hello,5:world,");
+/// let mut frames = tokio_netstring::parse_iter_bytes(buf);
+///
+/// assert_eq!(frames.next().unwrap().unwrap(), "hello");
+/// assert_eq!(frames.next().unwrap().unwrap(), "world");
+/// assert!(frames.next().is_none());
+/// ```
+pub fn parse_iter_bytes(buf: Bytes) -> ParseIterBytes {
+    Builder::new().new_parse_iter_bytes(buf)
+}
+
+// ===== impl ParseIter =====
+
+impl<'a> ParseIter<'a> {
+    pub(crate) fn new(buf: &'a [u8], builder: Builder) -> ParseIter<'a> {
+        ParseIter { buf, cursor: Cursor::new(builder) }
+    }
+
+    /// Returns the part of the buffer not parsed yet.
+    pub fn remainder(&self) -> &'a [u8] {
+        &self.buf[self.cursor.pos..]
+    }
+}
+
+impl<'a> Iterator for ParseIter<'a> {
+    type Item = Result<&'a [u8], NetstringError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let buf = self.buf;
+        self.cursor.next_frame(buf).map(|res| res.map(|range| &buf[range]))
+    }
+}
+
+impl FusedIterator for ParseIter<'_> {}
+
+// ===== impl ParseIterBytes =====
+
+impl ParseIterBytes {
+    pub(crate) fn new(buf: Bytes, builder: Builder) -> ParseIterBytes {
+        ParseIterBytes { buf, cursor: Cursor::new(builder) }
+    }
+
+    /// Returns the part of the buffer not parsed yet.
+    pub fn remainder(&self) -> Bytes {
+        self.buf.slice(self.cursor.pos..)
+    }
+
+    pub(crate) fn builder(&self) -> &Builder {
+        &self.cursor.builder
+    }
+}
+
+impl Iterator for ParseIterBytes {
+    type Item = Result<Bytes, NetstringError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let range = self.cursor.next_frame(&self.buf)?;
+        Some(range.map(|range| self.buf.slice(range)))
+    }
+}
+
+impl FusedIterator for ParseIterBytes {}
+
+// ===== impl Cursor =====
+
+impl Cursor {
+    fn new(builder: Builder) -> Cursor {
+        Cursor { builder, pos: 0, done: false }
+    }
+
+    // Find the next frame in `buf`, returning the range to yield
+    fn next_frame(&mut self, buf: &[u8]) -> Option<Result<Range<usize>, NetstringError>> {
+        let e = loop {
+            if self.done || self.pos == buf.len() {
+                self.done = true;
+                return None;
+            }
+
+            let input = &buf[self.pos..];
+            let mut state = ParseState::default();
+
+            match state.parse(&self.builder, input) {
+                Status::Frame(payload) => {
+                    let start = self.pos;
+                    self.pos += payload.end + NETSTRING_TAIL.len();
+
+                    if self.builder.strip_frame {
+                        return Some(Ok(start + payload.start..start + payload.end));
+                    }
+
+                    return Some(Ok(start..self.pos));
+                }
+                Status::Oversize { len, .. } if self.builder.on_oversize == OversizePolicy::Stream => {
+                    // The payload cannot be handed over in chunks
+                    break NetstringError::FrameTooLarge { declared: len, max: self.builder.max_frame_len };
+                }
+                Status::Oversize { head, len } => {
+                    // Note: there is a ',' after the payload
+                    let end = head as u64 + len;
+
+                    if end >= input.len() as u64 {
+                        break NetstringError::UnexpectedEof {
+                            expected: Some(usize::try_from(end + 1 - input.len() as u64).unwrap_or(usize::MAX)),
+                            buffered: input.len(),
+                        };
+                    }
+
+                    let found = input[end as usize];
+
+                    if !self.builder.terminators.contains(&found) {
+                        break NetstringError::MissingTerminator { found, expected: self.builder.terminators };
+                    }
+
+                    self.pos += end as usize + NETSTRING_TAIL.len();
+                }
+                Status::NeedMore(_) => {
+                    break NetstringError::UnexpectedEof {
+                        expected: state.frame_len().map(|frame_len| frame_len - input.len()),
+                        buffered: input.len(),
+                    };
+                }
+                Status::Error(e) => break e,
+            }
+        };
+
+        match e {
+            NetstringError::LengthParse { .. } |
+            NetstringError::FrameTooLarge { .. } |
+            NetstringError::MissingTerminator { .. } if self.builder.resync => {
+                Some(Err(self.resync(buf)))
+            }
+            e => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+
+    // Discard bytes until the buffer resumes with a complete frame, the
+    // rest of the buffer is discarded if none is found
+    fn resync(&mut self, buf: &[u8]) -> NetstringError {
+        let offset = self.pos;
+        self.pos += 1;
+
+        while self.pos < buf.len() {
+            match ParseState::default().parse(&self.builder, &buf[self.pos..]) {
+                Status::Frame(_) | Status::Oversize { .. } => break,
+                Status::Error(_) => self.pos += 1,
+                Status::NeedMore(_) => self.pos = buf.len(),
+            }
+        }
+
+        NetstringError::Corrupt { offset: offset as u64, skipped: (self.pos - offset) as u64 }
+    }
+}
@@ -16,19 +16,15 @@
 //! configuration values.
 //!
 //! ```
-//! # extern crate tokio_io;
-//! # extern crate tokio_netstring;
-//! #
-//! use tokio_io::{AsyncRead, AsyncWrite};
+//! use tokio::io::{AsyncRead, AsyncWrite};
 //! use tokio_netstring as netstring;
 //!
+//! # #[cfg(feature = "tokio")]
 //! fn bind_transport<T: AsyncRead + AsyncWrite>(io: T)
 //!     -> netstring::Framed<T>
 //! {
 //!     netstring::Framed::new(io)
 //! }
-//! #
-//! # fn main() {}
 //! ```
 //!
 //! The returned transport implements `Sink + Stream` for `BytesMut`. It
@@ -43,24 +39,18 @@
 //! Specifically, given the following:
 //!
 //! ```
-//! # extern crate tokio_io;
-//! # extern crate tokio_netstring;
-//! # extern crate bytes;
-//! # extern crate futures;
-//! #
-//! use tokio_io::{AsyncRead, AsyncWrite};
+//! use tokio::io::{AsyncRead, AsyncWrite};
 //! use tokio_netstring as netstring;
 //! use bytes::BytesMut;
-//! use futures::{Sink, Future};
+//! use futures::SinkExt;
 //!
-//! fn write_frame<T: AsyncRead + AsyncWrite>(io: T) {
+//! # #[cfg(feature = "tokio")]
+//! async fn write_frame<T: AsyncRead + AsyncWrite + Unpin>(io: T) {
 //!     let mut transport = netstring::Framed::new(io);
 //!     let frame = BytesMut::from("hello world");
 //!
-//!     transport.send(frame).wait().unwrap();
+//!     transport.send(frame).await.unwrap();
 //! }
-//! #
-//! # pub fn main() {}
 //! ```
 //!
 //! The encoded frame will look like this:
@@ -75,8 +65,8 @@
 //!
 //! [`FramedRead`] adapts an [`AsyncRead`] into a `Stream` of [`BytesMut`],
 //! such that each yielded [`BytesMut`] value contains the contents of an
-//! entire frame. There are many configuration paramaters enabling
-//! [`FrameRead`] to handle a wide range of protocols. Here are some
+//! entire frame. There are many configuration parameters enabling
+//! [`FramedRead`] to handle a wide range of protocols. Here are some
 //! examples that will cover the various options at a high level.
 //!
 //! ## Example 1
@@ -85,18 +75,15 @@
 //! frame head in the yielded `BytesMut`.
 //!
 //! ```
-//! # extern crate tokio_io;
-//! # extern crate tokio_netstring;
-//! #
-//! # use tokio_io::AsyncRead;
+//! # use tokio::io::AsyncRead;
 //! # use tokio_netstring as netstring;
+//! # #[cfg(feature = "tokio")]
 //! # fn bind_read<T: AsyncRead>(io: T) {
 //! netstring::Builder::new()
 //!     .length_field_offset(0) // default value
 //!     .strip_frame(false) // Do not strip frame header
 //!     .new_read(io);
 //! # }
-//! # fn main() {}
 //! ```
 //!
 //! The following frame will be decoded as such:
@@ -120,18 +107,15 @@
 //! frame head in the yielded `BytesMut`.
 //!
 //! ```
-//! # extern crate tokio_io;
-//! # extern crate tokio_netstring;
-//! #
-//! # use tokio_io::AsyncRead;
+//! # use tokio::io::AsyncRead;
 //! # use tokio_netstring as netstring;
+//! # #[cfg(feature = "tokio")]
 //! # fn bind_read<T: AsyncRead>(io: T) {
 //! netstring::Builder::new()
 //!     .length_field_offset(0) // default value
 //!     // `strip_frame` is not needed, the default is to skip
 //!     .new_read(io);
 //! # }
-//! # fn main() {}
 //! ```
 //!
 //! The following frame will be decoded as such:
@@ -153,18 +137,15 @@
 //! yielded `BytesMut`.
 //!
 //! ```
-//! # extern crate tokio_io;
-//! # extern crate tokio_netstring;
-//! #
-//! # use tokio_io::AsyncRead;
+//! # use tokio::io::AsyncRead;
 //! # use tokio_netstring as netstring;
+//! # #[cfg(feature = "tokio")]
 //! # fn bind_read<T: AsyncRead>(io: T) {
 //! netstring::Builder::new()
 //!     .length_field_offset(1) // length of hdr1
 //!     // `strip_frame` is not needed, the default is to skip
 //!     .new_read(io);
 //! # }
-//! # fn main() {}
 //! ```
 //!
 //! The following frame will be decoded as such:
@@ -196,19 +177,15 @@
 //! configuration:
 //!
 //! ```
-//! # extern crate tokio_io;
-//! # extern crate tokio_netstring;
-//! # extern crate bytes;
-//! #
-//! # use tokio_io::AsyncWrite;
+//! # use tokio::io::AsyncWrite;
 //! # use tokio_netstring as netstring;
 //! # use bytes::BytesMut;
+//! # #[cfg(feature = "tokio")]
 //! # fn write_frame<T: AsyncWrite>(io: T) {
 //! # let _: netstring::FramedWrite<T, BytesMut> =
 //! netstring::Builder::new()
 //!     .new_write(io);
 //! # }
-//! # pub fn main() {}
 //! ```
 //!
 //! A payload of `hello world` will be encoded as:
@@ -219,40 +196,154 @@
 //! +---------------+-+-------------+-+
 //! ```
 //!
-//! [`FramedRead`]: struct.FramedRead.html
-//! [`FramedWrite`]: struct.FramedWrite.html
-//! [`AsyncRead`]: ../../trait.AsyncRead.html
-//! [`AsyncWrite`]: ../../trait.AsyncWrite.html
-//! [`Encoder`]: ../trait.Encoder.html
-//! [`BytesMut`]: https://docs.rs/bytes/~0.4/bytes/struct.BytesMut.html
+//! # Codec
+//!
+//! The framing logic is also available as [`NetstringDecoder`] and
+//! [`NetstringEncoder`], which implement the `tokio-util` [`Decoder`] and
+//! [`Encoder`] traits and can be used directly with
+//! [`tokio_util::codec::Framed`] and friends, or stacked with other codecs.
+//! [`NetstringCodec`] bundles both halves into a single value.
+//!
+//! # Tagged netstrings
+//!
+//! The [`tnetstring`] module handles tnetstrings, netstrings ending with a
+//! type tag in place of the `','`, as typed values. They are parsed by the
+//! same code as netstrings, and [`Builder::new_tnetstring_read`] creates a
+//! `FramedRead` yielding them.
+//!
+//! # Runtime independent core
+//!
+//! Every decoder is built on [`Parser`], which is handed byte slices and
+//! reports `Status::NeedMore`, `Status::Frame` or `Status::Error` without
+//! doing any I/O, and every encoder on [`NetstringEncoder::encode_buf`],
+//! which writes into any `BufMut`. The blocking [`sync`] front end and the
+//! `futures-io` adapters, enabled by the `futures-io` feature, share them
+//! with the tokio types.
+//!
+//! # Features
+//!
+//! - `std` (default): `std::io` integration, the [`sync`] front end and
+//!   the `std::error::Error` implementation.
+//! - `tokio` (default): the tokio and `tokio-util` types, implies `std`.
+//! - `futures-io`: adapters for `futures-io` readers and writers, implies
+//!   `tokio`.
+//! - `serde`: the [`serde`] data format, encoding values as nested
+//!   netstrings, and serde support for [`tnetstring`] values. It does not
+//!   require `std`.
+//!
+//! With default features disabled, the crate is `no_std` and only requires
+//! `alloc`. [`Parser`], [`NetstringEncoder::encode_buf`] and
+//! [`NetstringDecoder::decode_buf`] remain available.
+//!
+//! [`AsyncRead`]: https://docs.rs/tokio/1/tokio/io/trait.AsyncRead.html
+//! [`AsyncWrite`]: https://docs.rs/tokio/1/tokio/io/trait.AsyncWrite.html
+//! [`Decoder`]: https://docs.rs/tokio-util/0.7/tokio_util/codec/trait.Decoder.html
+//! [`Encoder`]: https://docs.rs/tokio-util/0.7/tokio_util/codec/trait.Encoder.html
+//! [`BytesMut`]: https://docs.rs/bytes/1/bytes/struct.BytesMut.html
 
-extern crate bytes;
-#[macro_use]
-extern crate futures;
-#[macro_use]
-extern crate tokio_io;
+#![cfg_attr(not(feature = "std"), no_std)]
 
-use tokio_io::{codec, AsyncRead, AsyncWrite};
+extern crate alloc;
 
-use bytes::{Buf, BufMut, BytesMut, IntoBuf};
 use bytes::buf::Chain;
-
-use futures::{Async, AsyncSink, Stream, Sink, StartSend, Poll};
-
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+#[cfg(feature = "tokio")]
+use futures_core::Stream;
+#[cfg(feature = "tokio")]
+use futures_sink::Sink;
+
+#[cfg(feature = "tokio")]
+use pin_project_lite::pin_project;
+
+#[cfg(feature = "tokio")]
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+#[cfg(feature = "tokio")]
+use tokio_util::codec;
+#[cfg(feature = "tokio")]
+use tokio_util::io::poll_read_buf;
+
+#[cfg(feature = "tokio")]
+use std::collections::VecDeque;
+#[cfg(feature = "tokio")]
 use std::fmt;
-use std::io::{self, Cursor};
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "tokio")]
+use std::io::IoSlice;
+#[cfg(feature = "tokio")]
+use std::pin::Pin;
+#[cfg(feature = "tokio")]
+use std::task::{ready, Context, Poll};
+
+#[cfg(feature = "tokio")]
+use crate::budget::Charge;
+
+#[cfg(feature = "futures-io")]
+use tokio_util::compat::{Compat, FuturesAsyncReadCompatExt, FuturesAsyncWriteCompatExt};
+
+#[cfg(feature = "tokio")]
+mod body;
+#[cfg(feature = "tokio")]
+mod budget;
+mod error;
+#[cfg(feature = "tokio")]
+mod event;
+#[cfg(feature = "tokio")]
+mod frame;
+mod iter;
+mod nested;
+mod oneshot;
+mod parser;
+#[cfg(feature = "tokio")]
+mod send;
+#[cfg(feature = "serde")]
+pub mod serde;
+#[cfg(feature = "std")]
+pub mod sync;
+pub mod tnetstring;
+
+#[cfg(feature = "tokio")]
+pub use crate::body::{Body, BodyStream};
+#[cfg(feature = "tokio")]
+pub use crate::budget::MemoryBudget;
+pub use crate::error::NetstringError;
+#[cfg(feature = "tokio")]
+pub use crate::event::{Event, EventDecoder};
+#[cfg(feature = "tokio")]
+pub use crate::frame::{Frame, FrameDecoder, Prefixed};
+pub use crate::iter::{parse_iter, parse_iter_bytes, ParseIter, ParseIterBytes};
+pub use crate::nested::{NestedReader, NestedWriter};
+pub use crate::oneshot::{decode_one, encode, encode_into, encoded_len};
+pub use crate::parser::{Parser, Status};
+
+use crate::parser::{Head, ParseState};
+use crate::tnetstring::{TnetstringDecoder, TnetstringEncoder};
 
 // The following empty netstring `0:,` is the smallest one
 const MINIMUM_NETSTRING: usize = 3;
 
-const NETSTRING_TAIL: &'static [u8] = &[b','];
+const NETSTRING_TAIL: &[u8] = b",";
+
+// Frames larger than this are not left sharing the read buffer allocation
+const LARGE_FRAME_LEN: usize = 64 * 1_024;
+
+// Enough room for the digits of `usize::MAX` followed by the ':'
+const MAX_HEAD_LEN: usize = 21;
+
+// Upper bound on the number of slices handed to a single vectored write
+const MAX_WRITE_SLICES: usize = 64;
+
+// Number of bytes the read buffer grows by before reading from the transport
+#[cfg(feature = "std")]
+const READ_CHUNK_LEN: usize = 8 * 1_024;
 
 /// Configure netstring delimited `FramedRead`, `FramedWrite`, and `Framed` values.
 ///
 /// `Builder` enables constructing configured netstring delimited framers. Note
 /// that not all configuration settings apply to both encoding and decoding. See
 /// the documentation for specific methods for more detail.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct Builder {
     // Maximum frame length
     max_frame_len: usize,
@@ -262,69 +353,267 @@ pub struct Builder {
 
     // Remove the length, ':' and trailing ','
     strip_frame: bool,
+
+    // Maximum number of bytes in the length field
+    max_length_digits: usize,
+
+    // Accept sloppy length fields
+    lenient: bool,
+
+    // Bytes written before the length field of every frame, if any
+    write_prefix: Option<Bytes>,
+
+    // High-water mark of the write queue, in bytes
+    write_buffer_bytes: usize,
+
+    // High-water mark of the write queue, in frames
+    max_queued_frames: usize,
+
+    // How the read buffer grows to hold a frame
+    reserve_strategy: ReserveStrategy,
+
+    // What to do with frames over the max frame length
+    on_oversize: OversizePolicy,
+
+    // Look for the next frame after a corrupt one
+    resync: bool,
+
+    // Bytes accepted after the payload, each ending a frame
+    terminators: &'static [u8],
+
+    // Memory shared with other framers
+    #[cfg(feature = "tokio")]
+    memory_budget: Option<MemoryBudget>,
+}
+
+/// How the read buffer grows to hold an incoming frame.
+///
+/// See [`Builder::reserve_strategy`] for more detail.
+///
+/// [`Builder::reserve_strategy`]: struct.Builder.html#method.reserve_strategy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReserveStrategy {
+    /// Reserve room for the whole frame as soon as its length is read.
+    Eager,
+
+    /// Grow the buffer by at most `chunk` bytes at a time, as the frame
+    /// arrives.
+    Incremental {
+        /// Maximum number of bytes reserved at once
+        chunk: usize,
+    },
 }
 
-/// Adapts a byte stream into a unified `Stream` and `Sink` that works over
-/// entire frame values.
+/// What the decoder does with a frame over the max frame length.
 ///
-/// See [module level] documentation for more detail.
+/// See [`Builder::on_oversize`] for more detail.
 ///
-/// [module level]: index.html
-pub struct Framed<T, B: IntoBuf = BytesMut> {
-    inner: FramedRead<FramedWrite<T, B>>,
+/// [`Builder::on_oversize`]: struct.Builder.html#method.on_oversize
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OversizePolicy {
+    /// Fail with `NetstringError::FrameTooLarge`, ending the stream.
+    Error,
+
+    /// Discard the frame as it arrives, without buffering it.
+    Skip,
+
+    /// Hand the payload over in chunks as it arrives.
+    Stream,
 }
 
-/// Adapts a byte stream to a `Stream` yielding entire frame values.
+#[cfg(feature = "tokio")]
+pin_project! {
+    /// Adapts a byte stream into a unified `Stream` and `Sink` that works over
+    /// entire frame values.
+    ///
+    /// See [module level] documentation for more detail.
+    ///
+    /// [module level]: index.html
+    pub struct Framed<T, B = BytesMut> {
+        #[pin]
+        inner: FramedRead<FramedWrite<T, B>>,
+    }
+}
+
+#[cfg(feature = "tokio")]
+pin_project! {
+    /// Adapts a byte stream to a `Stream` yielding entire frame values.
+    ///
+    /// By default frames are yielded as `BytesMut` values. A `FramedRead`
+    /// created with [`Builder::new_frame_read`] yields [`Frame`] values
+    /// instead.
+    ///
+    /// See [module level] documentation for more detail.
+    ///
+    /// [module level]: index.html
+    #[derive(Debug)]
+    pub struct FramedRead<T, D = NetstringDecoder> {
+        // I/O type
+        #[pin]
+        inner: T,
+
+        // Frame decoder
+        decoder: D,
+
+        // Bytes read from `inner` and not decoded yet
+        buffer: BytesMut,
+
+        // Read buffer memory held against the budget
+        charge: Charge,
+
+        // `inner` reached EOF
+        eof: bool,
+
+        // No more frames will be yielded, after EOF or an error
+        done: bool,
+    }
+}
+
+/// A netstring [`Decoder`] and [`Encoder`] for use with `tokio-util`'s
+/// framing types.
 ///
-/// See [module level] documentation for more detail.
+/// `NetstringCodec` pairs a [`NetstringDecoder`] with a
+/// [`NetstringEncoder`] sharing the same configuration. It is configured
+/// through [`Builder::new_codec`].
 ///
-/// [module level]: index.html
+/// [`Decoder`]: https://docs.rs/tokio-util/0.7/tokio_util/codec/trait.Decoder.html
+/// [`Encoder`]: https://docs.rs/tokio-util/0.7/tokio_util/codec/trait.Encoder.html
+#[cfg(feature = "tokio")]
 #[derive(Debug)]
-pub struct FramedRead<T> {
-    inner: codec::FramedRead<T, Decoder>,
+pub struct NetstringCodec {
+    decoder: NetstringDecoder,
+    encoder: NetstringEncoder,
 }
 
+/// Decodes netstring frames out of a `BytesMut`.
+///
+/// `NetstringDecoder` implements the `tokio-util` [`Decoder`] trait. It is
+/// the decoding half of [`FramedRead`] and can be used on its own to plug
+/// netstring framing into other codec based transports. It is configured
+/// through [`Builder::new_decoder`].
+///
+/// [`Decoder`]: https://docs.rs/tokio-util/0.7/tokio_util/codec/trait.Decoder.html
 #[derive(Debug)]
-struct Decoder {
+pub struct NetstringDecoder {
     // Configuration values
     builder: Builder,
 
     // Read state
     state: DecodeState,
-}
 
-#[derive(Debug, Clone, Copy)]
-enum DecodeState {
-    Head,
-    Data(usize),
+    // Stream offset of the first byte in the buffer
+    offset: u64,
+
+    // Oversized payloads can be handed over as events. Without events,
+    // `OversizePolicy::Stream` rejects oversized frames.
+    events: bool,
 }
 
-/// Adapts a byte stream to a `Sink` accepting entire frame values.
+/// Encodes payloads as netstring frames into a `BytesMut`.
 ///
-/// See [module level] documentation for more detail.
+/// `NetstringEncoder` implements the `tokio-util` [`Encoder`] trait for any
+/// `Buf` payload. It is configured through [`Builder::new_encoder`].
 ///
-/// [module level]: index.html
-pub struct FramedWrite<T, B: IntoBuf = BytesMut> {
-    // I/O type
-    inner: T,
-
+/// [`Encoder`]: https://docs.rs/tokio-util/0.7/tokio_util/codec/trait.Encoder.html
+#[derive(Debug, Clone)]
+pub struct NetstringEncoder {
     // Configuration values
     builder: Builder,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum DecodeState {
+    // Parsing the next frame
+    Parse(ParseState),
+    // Going through the payload of a frame over the max frame length, head
+    // already consumed. `offset` is the stream offset of the frame, `len`
+    // the declared payload length and `remaining` the number of payload
+    // bytes still to come.
+    Oversize { offset: u64, len: u64, remaining: u64 },
+    // Looking for the next frame after a corrupt one. `offset` is the
+    // stream offset of the first byte discarded and `skipped` the number of
+    // bytes discarded so far. The buffer starts with a possible frame.
+    Resync { offset: u64, skipped: u64 },
+}
+
+// What the decoder found next in the read buffer
+#[cfg_attr(not(feature = "tokio"), allow(dead_code))]
+enum RawEvent {
+    // A complete frame
+    Frame(RawFrame),
+
+    // An oversized frame was discarded
+    Skipped { offset: u64, len: u64 },
+
+    // Payload bytes of an oversized frame
+    Chunk { offset: u64, len: u64, data: BytesMut, last: bool },
+}
 
-    // Current frame being written
-    frame: Option<Chain<Chain<Cursor<BytesMut>, B::Buf>, Cursor<&'static [u8]>>>,
+// A complete frame, split off the read buffer
+#[cfg_attr(not(feature = "tokio"), allow(dead_code))]
+struct RawFrame {
+    // The whole frame, from the prefix to the trailing ','
+    buf: BytesMut,
+
+    // Number of bytes up to and including the `:`
+    head: usize,
+
+    // Declared payload length
+    len: usize,
+
+    // Stream offset of the first byte of the frame
+    offset: u64,
+}
+
+// A frame ready to be written: prefix, `len:`, payload and ','
+type EncodedFrame<B> = Chain<Chain<Chain<Bytes, Head>, B>, &'static [u8]>;
+
+#[cfg(feature = "tokio")]
+pin_project! {
+    /// Adapts a byte stream to a `Sink` accepting entire frame values.
+    ///
+    /// See [module level] documentation for more detail.
+    ///
+    /// [module level]: index.html
+    pub struct FramedWrite<T, B = BytesMut> {
+        // I/O type
+        #[pin]
+        inner: T,
+
+        // Frame head encoder
+        encoder: NetstringEncoder,
+
+        // Frames waiting to be written, oldest first
+        frames: VecDeque<EncodedFrame<B>>,
+
+        // Number of bytes left to write across all queued frames
+        queued: usize,
+
+        // Bytes left of the last queued frame, when it did not fit in the
+        // budget and is held outside of it until written
+        uncharged: usize,
+
+        // Set while a frame body is written straight to `inner`, and left
+        // set if the body is cut short
+        poisoned: bool,
+
+        // Write queue memory held against the budget
+        charge: Charge,
+    }
 }
 
 // ===== impl Framed =====
 
-impl<T: AsyncRead + AsyncWrite, B: IntoBuf> Framed<T, B> {
+#[cfg(feature = "tokio")]
+impl<T: AsyncRead + AsyncWrite, B: Buf> Framed<T, B> {
     /// Creates a new `Framed` with default configuration values.
     pub fn new(inner: T) -> Framed<T, B> {
         Builder::new().new_framed(inner)
     }
 }
 
-impl<T, B: IntoBuf> Framed<T, B> {
+#[cfg(feature = "tokio")]
+impl<T, B> Framed<T, B> {
     /// Returns a reference to the underlying I/O stream wrapped by `Framed`.
     ///
     /// Note that care should be taken to not tamper with the underlying stream
@@ -354,35 +643,40 @@ impl<T, B: IntoBuf> Framed<T, B> {
     }
 }
 
-impl<T: AsyncRead, B: IntoBuf> Stream for Framed<T, B> {
-    type Item = BytesMut;
-    type Error = io::Error;
+#[cfg(feature = "tokio")]
+impl<T: AsyncRead, B> Stream for Framed<T, B> {
+    type Item = Result<BytesMut, NetstringError>;
 
-    fn poll(&mut self) -> Poll<Option<BytesMut>, io::Error> {
-        self.inner.poll()
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.project().inner.poll_next(cx)
     }
 }
 
-impl<T: AsyncWrite, B: IntoBuf> Sink for Framed<T, B> {
-    type SinkItem = B;
-    type SinkError = io::Error;
+#[cfg(feature = "tokio")]
+impl<T: AsyncWrite, B: Buf> Sink<B> for Framed<T, B> {
+    type Error = NetstringError;
 
-    fn start_send(&mut self, item: B) -> StartSend<B, io::Error> {
-        self.inner.start_send(item)
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), NetstringError>> {
+        self.project().inner.poll_ready(cx)
     }
 
-    fn poll_complete(&mut self) -> Poll<(), io::Error> {
-        self.inner.poll_complete()
+    fn start_send(self: Pin<&mut Self>, item: B) -> Result<(), NetstringError> {
+        self.project().inner.start_send(item)
     }
 
-    fn close(&mut self) -> Poll<(), io::Error> {
-        self.inner.close()
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), NetstringError>> {
+        self.project().inner.poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), NetstringError>> {
+        self.project().inner.poll_close(cx)
     }
 }
 
-impl<T, B: IntoBuf> fmt::Debug for Framed<T, B>
+#[cfg(feature = "tokio")]
+impl<T, B> fmt::Debug for Framed<T, B>
     where T: fmt::Debug,
-          B::Buf: fmt::Debug
+          B: fmt::Debug
 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("Framed")
@@ -393,6 +687,7 @@ impl<T, B: IntoBuf> fmt::Debug for Framed<T, B>
 
 // ===== impl FramedRead =====
 
+#[cfg(feature = "tokio")]
 impl<T: AsyncRead> FramedRead<T> {
     /// Creates a new `FramedRead` with default configuration values.
     pub fn new(inner: T) -> FramedRead<T> {
@@ -400,14 +695,15 @@ impl<T: AsyncRead> FramedRead<T> {
     }
 }
 
-impl<T> FramedRead<T> {
+#[cfg(feature = "tokio")]
+impl<T, D> FramedRead<T, D> {
     /// Returns a reference to the underlying I/O stream wrapped by `FramedRead`.
     ///
     /// Note that care should be taken to not tamper with the underlying stream
     /// of data coming in as it may corrupt the stream of frames otherwise
     /// being worked with.
     pub fn get_ref(&self) -> &T {
-        self.inner.get_ref()
+        &self.inner
     }
 
     /// Returns a mutable reference to the underlying I/O stream wrapped by
@@ -417,7 +713,7 @@ impl<T> FramedRead<T> {
     /// of data coming in as it may corrupt the stream of frames otherwise being
     /// worked with.
     pub fn get_mut(&mut self) -> &mut T {
-        self.inner.get_mut()
+        &mut self.inner
     }
 
     /// Consumes the `FramedRead`, returning its underlying I/O stream.
@@ -426,334 +722,799 @@ impl<T> FramedRead<T> {
     /// of data coming in as it may corrupt the stream of frames otherwise being
     /// worked with.
     pub fn into_inner(self) -> T {
-        self.inner.into_inner()
+        self.inner
+    }
+
+    fn with_decoder(inner: T, decoder: D, budget: Option<MemoryBudget>) -> FramedRead<T, D> {
+        FramedRead {
+            inner,
+            decoder,
+            buffer: BytesMut::new(),
+            charge: Charge::new(budget),
+            eof: false,
+            done: false,
+        }
     }
 }
 
-impl<T: AsyncRead> Stream for FramedRead<T> {
-    type Item = BytesMut;
-    type Error = io::Error;
+#[cfg(feature = "tokio")]
+impl<T, D> Stream for FramedRead<T, D>
+    where T: AsyncRead,
+          D: codec::Decoder<Error = NetstringError>
+{
+    type Item = Result<D::Item, NetstringError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+
+        loop {
+            if *this.done {
+                return Poll::Ready(None);
+            }
+
+            // Decode the buffered bytes first, then drain them once the
+            // transport reached EOF
+            let res = if *this.eof {
+                this.decoder.decode_eof(this.buffer)
+            } else {
+                this.decoder.decode(this.buffer)
+            };
+
+            // Decoding only ever shrinks the buffer
+            this.charge.release_to(this.buffer.capacity());
+
+            match res {
+                Ok(Some(item)) => return Poll::Ready(Some(Ok(item))),
+                Ok(None) if *this.eof => {
+                    *this.done = true;
+                    return Poll::Ready(None);
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    // The decoder recovers from corrupt frames on its own
+                    if !matches!(e, NetstringError::Corrupt { .. }) {
+                        *this.done = true;
+                    }
+
+                    return Poll::Ready(Some(Err(e)));
+                }
+            }
 
-    fn poll(&mut self) -> Poll<Option<BytesMut>, io::Error> {
-        self.inner.poll()
+            if this.buffer.len() == this.buffer.capacity() {
+                // Grow the buffer, without going over the memory budget
+                ready!(this.charge.poll_reserve(cx, this.buffer, READ_CHUNK_LEN));
+            }
+
+            match ready!(poll_read_buf(this.inner.as_mut(), cx, this.buffer)) {
+                Ok(0) => *this.eof = true,
+                Ok(_) => {}
+                Err(e) => {
+                    *this.done = true;
+                    return Poll::Ready(Some(Err(e.into())));
+                }
+            }
+        }
     }
 }
 
-impl<T: Sink> Sink for FramedRead<T> {
-    type SinkItem = T::SinkItem;
-    type SinkError = T::SinkError;
+#[cfg(feature = "tokio")]
+impl<T: Sink<I>, I, D> Sink<I> for FramedRead<T, D> {
+    type Error = T::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), T::Error>> {
+        self.project().inner.poll_ready(cx)
+    }
 
-    fn start_send(&mut self, item: T::SinkItem) -> StartSend<T::SinkItem, T::SinkError> {
-        self.inner.start_send(item)
+    fn start_send(self: Pin<&mut Self>, item: I) -> Result<(), T::Error> {
+        self.project().inner.start_send(item)
     }
 
-    fn poll_complete(&mut self) -> Poll<(), T::SinkError> {
-        self.inner.poll_complete()
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), T::Error>> {
+        self.project().inner.poll_flush(cx)
     }
 
-    fn close(&mut self) -> Poll<(), T::SinkError> {
-        self.inner.close()
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), T::Error>> {
+        self.project().inner.poll_close(cx)
     }
 }
 
-impl<T: io::Write> io::Write for FramedRead<T> {
-    fn write(&mut self, src: &[u8]) -> io::Result<usize> {
-        self.inner.get_mut().write(src)
+// ===== impl NetstringCodec ======
+
+#[cfg(feature = "tokio")]
+impl NetstringCodec {
+    /// Creates a new `NetstringCodec` with default configuration values.
+    pub fn new() -> NetstringCodec {
+        Builder::new().new_codec()
+    }
+
+    /// Returns a reference to the decoding half of the codec.
+    pub fn decoder(&self) -> &NetstringDecoder {
+        &self.decoder
+    }
+
+    /// Returns a reference to the encoding half of the codec.
+    pub fn encoder(&self) -> &NetstringEncoder {
+        &self.encoder
     }
+}
 
-    fn flush(&mut self) -> io::Result<()> {
-        self.inner.get_mut().flush()
+#[cfg(feature = "tokio")]
+impl Default for NetstringCodec {
+    fn default() -> NetstringCodec {
+        NetstringCodec::new()
     }
 }
 
-impl<T: AsyncWrite> AsyncWrite for FramedRead<T> {
-    fn shutdown(&mut self) -> Poll<(), io::Error> {
-        self.inner.get_mut().shutdown()
+#[cfg(feature = "tokio")]
+impl codec::Decoder for NetstringCodec {
+    type Item = BytesMut;
+    type Error = NetstringError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<BytesMut>, NetstringError> {
+        self.decoder.decode(src)
     }
 
-    fn write_buf<B: Buf>(&mut self, buf: &mut B) -> Poll<usize, io::Error> {
-        self.inner.get_mut().write_buf(buf)
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<BytesMut>, NetstringError> {
+        self.decoder.decode_eof(src)
     }
 }
 
-// ===== impl Decoder ======
+#[cfg(feature = "tokio")]
+impl<B: Buf> codec::Encoder<B> for NetstringCodec {
+    type Error = NetstringError;
 
-impl Decoder {
-    fn decode_head(&mut self, src: &mut BytesMut) -> io::Result<Option<usize>> {
-        if src.len() < self.builder.length_field_offset + MINIMUM_NETSTRING {
-            // Not enough data
-            return Ok(None);
-        }
+    fn encode(&mut self, item: B, dst: &mut BytesMut) -> Result<(), NetstringError> {
+        self.encoder.encode(item, dst)
+    }
+}
 
-        let (n, i) = {
-            let mut src = Cursor::new(&mut *src);
-
-            // Skip the required bytes
-            src.advance(self.builder.length_field_offset);
-
-            // Find the next `:` delimiting the end of the length
-            if let Some(i) = src.bytes().iter().position(|b| *b == b':') {
-                // Parse length
-                let n: u64 = match String::from_utf8(src.bytes()[..i].to_vec()) {
-                    Ok(s) => {
-                        s.parse()
-                            .map_err(|_| {
-                                io::Error::new(io::ErrorKind::InvalidData, "Could not parse length")
-                            })?
-                    }
-                    Err(err) => return Err(io::Error::new(io::ErrorKind::InvalidData, err)),
-                };
+#[cfg(feature = "tokio")]
+impl<B: Buf> codec::Encoder<Prefixed<B>> for NetstringCodec {
+    type Error = NetstringError;
 
-                if n > self.builder.max_frame_len as u64 {
-                    return Err(io::Error::new(io::ErrorKind::InvalidData, "frame size too big"));
-                }
+    fn encode(&mut self, item: Prefixed<B>, dst: &mut BytesMut) -> Result<(), NetstringError> {
+        self.encoder.encode(item, dst)
+    }
+}
 
-                // The check above ensures there is no overflow
-                (n as usize, i)
-            } else {
-                return Ok(None);
-            }
-        };
+// ===== impl NetstringDecoder ======
 
-        if self.builder.strip_frame {
-            // | length_field_offset | netstring |':'| payload
-            let num_skip = self.builder.length_field_offset + i + 1;
-            let _ = src.split_to(num_skip);
-        }
+impl NetstringDecoder {
+    /// Creates a new `NetstringDecoder` with default configuration values.
+    pub fn new() -> NetstringDecoder {
+        Builder::new().new_decoder()
+    }
 
-        // Ensure that the buffer has enough space to read the incoming
-        // payload
-        // Note: there is a ',' after the payload
-        src.reserve(n + 1);
+    /// Decodes the next frame out of `src`.
+    ///
+    /// This is the runtime independent counterpart of `Decoder::decode`.
+    /// The frame is split off `src`, `None` is returned if `src` does not
+    /// hold a complete frame yet.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokio_netstring::NetstringDecoder;
+    /// use bytes::BytesMut;
+    ///
+    /// let mut decoder = NetstringDecoder::new();
+    /// let mut src = BytesMut::from(&b"5:hello,5:wor"[..]);
+    ///
+    /// assert_eq!(decoder.decode_buf(&mut src).unwrap().unwrap(), &b"hello"[..]);
+    /// assert!(decoder.decode_buf(&mut src).unwrap().is_none());
+    /// ```
+    pub fn decode_buf(&mut self, src: &mut BytesMut) -> Result<Option<BytesMut>, NetstringError> {
+        match self.decode_frame(src)? {
+            Some(mut frame) => {
+                if self.builder.strip_frame {
+                    // Keep only the content
+                    frame.buf.advance(frame.head);
+                    frame.buf.truncate(frame.len);
+                }
 
-        return Ok(Some(n));
+                Ok(Some(frame.buf))
+            }
+            None => Ok(None),
+        }
     }
 
-    fn decode_data(&self, n: usize, src: &mut BytesMut) -> io::Result<Option<BytesMut>> {
-        // At this point, the buffer has already had the required capacity
-        // reserved. All there is to do is read.
-        // Note: The `+1` is for the ',' after the payload
-        if src.len() < n + 1 {
-            return Ok(None);
+    /// Decodes the next frame out of `src`, once no more bytes will come.
+    ///
+    /// This is the runtime independent counterpart of `Decoder::decode_eof`.
+    /// Bytes left over in `src` that do not form a complete frame are
+    /// reported as `NetstringError::UnexpectedEof`.
+    pub fn decode_buf_eof(&mut self, src: &mut BytesMut) -> Result<Option<BytesMut>, NetstringError> {
+        match self.decode_buf(src)? {
+            Some(frame) => Ok(Some(frame)),
+            None if !self.in_frame(src) => Ok(None),
+            None => Err(self.eof_error(src)),
         }
+    }
 
-        if self.builder.strip_frame {
-            // Get the content
-            let content = src.split_to(n);
+    // Decode the next complete frame in `src`, head and terminator included.
+    // Oversized frames are dropped.
+    fn decode_frame(&mut self, src: &mut BytesMut) -> Result<Option<RawFrame>, NetstringError> {
+        loop {
+            match self.decode_event(src)? {
+                Some(RawEvent::Frame(frame)) => return Ok(Some(frame)),
+                Some(_) => {}
+                None => return Ok(None),
+            }
+        }
+    }
 
-            // Remove the ',' at the end
-            let _ = src.split_to(1);
+    // Decode the next complete frame in `src`, or the next event of an
+    // oversized frame. Corrupt frames are skipped in resync mode.
+    fn decode_event(&mut self, src: &mut BytesMut) -> Result<Option<RawEvent>, NetstringError> {
+        if let DecodeState::Resync { offset, skipped } = self.state {
+            return self.decode_resync(offset, skipped, src);
+        }
 
-            Ok(Some(content))
-        } else {
-            Ok(Some(src.take()))
+        match self.decode_next(src) {
+            Err(NetstringError::LengthParse { .. }) |
+            Err(NetstringError::FrameTooLarge { .. }) |
+            Err(NetstringError::MissingTerminator { .. }) if self.builder.resync => {
+                // Discard the first byte of the corrupt frame and look for
+                // the next one from there
+                src.advance(1);
+                self.state = DecodeState::Resync { offset: self.offset, skipped: 1 };
+                self.offset += 1;
+
+                self.decode_event(src)
+            }
+            res => res,
         }
     }
-}
 
-impl codec::Decoder for Decoder {
-    type Item = BytesMut;
-    type Error = io::Error;
-
-    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<BytesMut>> {
-        let n = match self.state {
-            DecodeState::Head => {
-                match try!(self.decode_head(src)) {
-                    Some(n) => {
-                        self.state = DecodeState::Data(n);
-                        n
+    fn decode_next(&mut self, src: &mut BytesMut) -> Result<Option<RawEvent>, NetstringError> {
+        let mut parse = match self.state {
+            DecodeState::Parse(parse) => parse,
+            DecodeState::Oversize { .. } => return self.decode_oversize(src),
+            DecodeState::Resync { .. } => unreachable!(),
+        };
+
+        let status = parse.parse(&self.builder, src);
+        self.state = DecodeState::Parse(parse);
+
+        match status {
+            Status::NeedMore(_) => {
+                if let ParseState::Data { head, len } = parse {
+                    // A frame the whole budget cannot hold would never
+                    // complete
+                    #[cfg(feature = "tokio")]
+                    if let Some(ref budget) = self.builder.memory_budget {
+                        let max = budget.limit().saturating_sub(head + NETSTRING_TAIL.len());
+
+                        if len > max {
+                            self.state = DecodeState::Parse(ParseState::default());
+                            return Err(NetstringError::FrameTooLarge { declared: len as u64, max });
+                        }
                     }
-                    None => return Ok(None),
+
+                    // Make room for the next bytes of the frame
+                    self.reserve_frame(head + len + NETSTRING_TAIL.len(), src);
                 }
-            }
-            DecodeState::Data(n) => n,
-        };
 
-        match try!(self.decode_data(n, src)) {
-            Some(data) => {
-                // Update the decode state
-                self.state = DecodeState::Head;
+                Ok(None)
+            }
+            Status::Frame(payload) => {
+                // Take exactly one frame, leaving any following frames
+                // buffered
+                let buf = src.split_to(payload.end + NETSTRING_TAIL.len());
+
+                let offset = self.offset;
+                self.offset += buf.len() as u64;
+
+                if buf.len() > LARGE_FRAME_LEN {
+                    // Move the remaining bytes to a fresh buffer, so the
+                    // large allocation is released along with the frame
+                    let capacity = if self.budgeted() { src.len() } else { src.len().max(LARGE_FRAME_LEN / 8) };
+                    let mut fresh = BytesMut::with_capacity(capacity);
+                    fresh.extend_from_slice(src);
+                    *src = fresh;
+                }
 
                 // Make sure the buffer has enough space to read the next head
-                src.reserve(self.builder.length_field_offset + MINIMUM_NETSTRING);
+                if !self.budgeted() {
+                    src.reserve(self.builder.length_field_offset + MINIMUM_NETSTRING);
+                }
 
-                Ok(Some(data))
+                Ok(Some(RawEvent::Frame(RawFrame {
+                    buf,
+                    head: payload.start,
+                    len: payload.len(),
+                    offset,
+                })))
             }
-            None => Ok(None),
+            Status::Oversize { len, .. } if self.builder.on_oversize == OversizePolicy::Stream && !self.events => {
+                // Nothing would get the chunks, reject the frame rather than
+                // dropping its payload
+                Err(NetstringError::FrameTooLarge { declared: len, max: self.builder.max_frame_len })
+            }
+            Status::Oversize { head, len } => {
+                // Drop the head, the payload is dealt with as it arrives
+                src.advance(head);
+                self.state = DecodeState::Oversize { offset: self.offset, len, remaining: len };
+                self.offset += head as u64;
+
+                self.decode_oversize(src)
+            }
+            Status::Error(e) => Err(e),
         }
     }
-}
 
-// ===== impl FramedWrite =====
-
-impl<T: AsyncWrite, B: IntoBuf> FramedWrite<T, B> {
-    /// Creates a new `FramedWrite` with default configuration values.
-    pub fn new(inner: T) -> FramedWrite<T, B> {
-        Builder::new().new_write(inner)
-    }
-}
+    // Skip or stream the buffered payload bytes of an oversized frame
+    fn decode_oversize(&mut self, src: &mut BytesMut) -> Result<Option<RawEvent>, NetstringError> {
+        let (offset, len, remaining) = match self.state {
+            DecodeState::Oversize { offset, len, remaining } => (offset, len, remaining),
+            _ => unreachable!(),
+        };
 
-impl<T, B: IntoBuf> FramedWrite<T, B> {
-    /// Returns a reference to the underlying I/O stream wrapped by
-    /// `FramedWrite`.
-    ///
-    /// Note that care should be taken to not tamper with the underlying stream
-    /// of data coming in as it may corrupt the stream of frames otherwise
-    /// being worked with.
-    pub fn get_ref(&self) -> &T {
-        &self.inner
-    }
+        // The payload is complete once the ',' is buffered as well
+        let n = remaining.min(src.len() as u64) as usize;
+        let last = remaining < src.len() as u64;
 
-    /// Returns a mutable reference to the underlying I/O stream wrapped by
-    /// `FramedWrite`.
-    ///
-    /// Note that care should be taken to not tamper with the underlying stream
-    /// of data coming in as it may corrupt the stream of frames otherwise being
-    /// worked with.
-    pub fn get_mut(&mut self) -> &mut T {
-        &mut self.inner
-    }
+        if last && !self.builder.terminators.contains(&src[n]) {
+            return Err(NetstringError::MissingTerminator { found: src[n], expected: self.builder.terminators });
+        }
 
-    /// Consumes the `FramedWrite`, returning its underlying I/O stream.
-    ///
-    /// Note that care should be taken to not tamper with the underlying stream
-    /// of data coming in as it may corrupt the stream of frames otherwise being
-    /// worked with.
-    pub fn into_inner(self) -> T {
-        self.inner
-    }
-}
+        let data = match self.builder.on_oversize {
+            OversizePolicy::Stream => src.split_to(n),
+            _ => {
+                src.advance(n);
+                BytesMut::new()
+            }
+        };
 
-impl<T: AsyncWrite, B: IntoBuf> FramedWrite<T, B> {
-    // If there is a buffered frame, try to write it to `T`
-    fn do_write(&mut self) -> Poll<(), io::Error> {
-        if self.frame.is_none() {
-            return Ok(Async::Ready(()));
+        if last {
+            src.advance(NETSTRING_TAIL.len());
+            self.state = DecodeState::Parse(ParseState::default());
+        } else {
+            self.state = DecodeState::Oversize { offset, len, remaining: remaining - n as u64 };
         }
 
-        loop {
-            let frame = self.frame.as_mut().unwrap();
-            try_ready!(self.inner.write_buf(frame));
+        self.offset += (n + if last { NETSTRING_TAIL.len() } else { 0 }) as u64;
 
-            if !frame.has_remaining() {
-                break;
-            }
+        match self.builder.on_oversize {
+            OversizePolicy::Stream if last || n > 0 => Ok(Some(RawEvent::Chunk { offset, len, data, last })),
+            OversizePolicy::Skip if last => Ok(Some(RawEvent::Skipped { offset, len })),
+            _ => Ok(None),
         }
+    }
 
-        self.frame = None;
+    // Whether the read buffer is grown by `FramedRead` within a memory
+    // budget, rather than by the decoder
+    fn budgeted(&self) -> bool {
+        #[cfg(feature = "tokio")]
+        return self.builder.memory_budget.is_some();
 
-        Ok(Async::Ready(()))
+        #[cfg(not(feature = "tokio"))]
+        return false;
     }
 
-    fn set_frame(&mut self, buf: B::Buf) -> io::Result<()> {
-        let mut head = BytesMut::with_capacity(8);
-        let n = buf.remaining();
-
-        if n > self.builder.max_frame_len {
-            return Err(io::Error::new(io::ErrorKind::InvalidInput, "frame too big"));
+    // Grow `src` towards holding a whole frame of `frame_len` bytes,
+    // according to the configured reserve strategy
+    fn reserve_frame(&self, frame_len: usize, src: &mut BytesMut) {
+        if self.budgeted() {
+            return;
         }
 
-        let netstring = format!("{}:", n);
-        head.put_slice(netstring.as_bytes());
-
-        debug_assert!(self.frame.is_none());
+        let needed = frame_len.saturating_sub(src.len());
 
-        self.frame = Some(head.into_buf().chain(buf).chain(NETSTRING_TAIL));
-
-        Ok(())
+        match self.builder.reserve_strategy {
+            ReserveStrategy::Eager => src.reserve(needed),
+            ReserveStrategy::Incremental { chunk } => src.reserve(needed.min(chunk)),
+        }
     }
-}
 
-impl<T: AsyncWrite, B: IntoBuf> Sink for FramedWrite<T, B> {
-    type SinkItem = B;
-    type SinkError = io::Error;
-
-    fn start_send(&mut self, item: B) -> StartSend<B, io::Error> {
-        if !try!(self.do_write()).is_ready() {
-            return Ok(AsyncSink::NotReady(item));
+    // Discard bytes until `src` starts with a complete frame, then report
+    // the bytes discarded
+    fn decode_resync(&mut self, offset: u64, mut skipped: u64, src: &mut BytesMut)
+        -> Result<Option<RawEvent>, NetstringError>
+    {
+        loop {
+            match self.probe_frame(src) {
+                Some(true) => {
+                    self.state = DecodeState::Parse(ParseState::default());
+                    return Err(NetstringError::Corrupt { offset, skipped });
+                }
+                Some(false) => {
+                    src.advance(1);
+                    self.offset += 1;
+                    skipped += 1;
+                }
+                None => {
+                    self.state = DecodeState::Resync { offset, skipped };
+                    return Ok(None);
+                }
+            }
         }
+    }
 
-        try!(self.set_frame(item.into_buf()));
+    // Whether `src` starts with a complete and correctly terminated frame,
+    // or `None` if more bytes are needed to tell
+    fn probe_frame(&self, src: &BytesMut) -> Option<bool> {
+        match ParseState::default().parse(&self.builder, src) {
+            // An oversized frame to skip or stream cannot be checked without
+            // buffering it
+            Status::Frame(_) | Status::Oversize { .. } => Some(true),
+            Status::Error(_) => Some(false),
+            Status::NeedMore(_) => None,
+        }
+    }
 
-        Ok(AsyncSink::Ready)
+    // Whether the stream is in the middle of a frame, with `src` buffered
+    fn in_frame(&self, src: &BytesMut) -> bool {
+        !src.is_empty() || matches!(self.state, DecodeState::Oversize { .. } | DecodeState::Resync { .. })
     }
 
-    fn poll_complete(&mut self) -> Poll<(), io::Error> {
-        // Write any buffered frame to T
-        try_ready!(self.do_write());
+    // The error to report when the stream ends in the middle of a frame
+    fn eof_error(&mut self, src: &mut BytesMut) -> NetstringError {
+        if let DecodeState::Resync { offset, skipped } = self.state {
+            // Discard the rest of the stream
+            let skipped = skipped + src.len() as u64;
 
-        // Try flushing the underlying IO
-        try_nb!(self.inner.flush());
+            self.offset += src.len() as u64;
+            self.state = DecodeState::Parse(ParseState::default());
+            src.clear();
+
+            return NetstringError::Corrupt { offset, skipped };
+        }
+
+        let expected = match self.state {
+            DecodeState::Parse(parse) => parse.frame_len().map(|frame_len| frame_len - src.len()),
+            // Note: there is a ',' after the payload
+            DecodeState::Oversize { remaining, .. } => {
+                Some(usize::try_from(remaining.saturating_add(1) - src.len() as u64).unwrap_or(usize::MAX))
+            }
+            DecodeState::Resync { .. } => None,
+        };
 
-        return Ok(Async::Ready(()));
+        NetstringError::UnexpectedEof { expected, buffered: src.len() }
     }
+}
 
-    fn close(&mut self) -> Poll<(), io::Error> {
-        try_ready!(self.poll_complete());
-        self.inner.shutdown()
+impl Default for NetstringDecoder {
+    fn default() -> NetstringDecoder {
+        NetstringDecoder::new()
     }
 }
 
-impl<T: Stream, B: IntoBuf> Stream for FramedWrite<T, B> {
-    type Item = T::Item;
-    type Error = T::Error;
+#[cfg(feature = "tokio")]
+impl codec::Decoder for NetstringDecoder {
+    type Item = BytesMut;
+    type Error = NetstringError;
 
-    fn poll(&mut self) -> Poll<Option<T::Item>, T::Error> {
-        self.inner.poll()
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<BytesMut>, NetstringError> {
+        self.decode_buf(src)
     }
-}
 
-impl<T: io::Read, B: IntoBuf> io::Read for FramedWrite<T, B> {
-    fn read(&mut self, dst: &mut [u8]) -> io::Result<usize> {
-        self.get_mut().read(dst)
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<BytesMut>, NetstringError> {
+        self.decode_buf_eof(src)
     }
 }
 
-impl<T: AsyncRead, U: IntoBuf> AsyncRead for FramedWrite<T, U> {
-    fn read_buf<B: BufMut>(&mut self, buf: &mut B) -> Poll<usize, io::Error> {
-        self.get_mut().read_buf(buf)
+// ===== impl NetstringEncoder ======
+
+impl NetstringEncoder {
+    /// Creates a new `NetstringEncoder` with default configuration values.
+    pub fn new() -> NetstringEncoder {
+        Builder::new().new_encoder()
     }
 
-    unsafe fn prepare_uninitialized_buffer(&self, buf: &mut [u8]) -> bool {
-        self.get_ref().prepare_uninitialized_buffer(buf)
+    // Build the `prefix len:` head of a frame carrying `n` bytes of payload
+    fn encode_head(&self, prefix: Option<Bytes>, n: usize) -> Result<Chain<Bytes, Head>, NetstringError> {
+        if let Some(ref prefix) = prefix {
+            self.builder.check_prefix(prefix)?;
+        }
+
+        if n > self.builder.max_frame_len {
+            return Err(NetstringError::FrameTooLarge {
+                declared: n as u64,
+                max: self.builder.max_frame_len,
+            });
+        }
+
+        Ok(prefix.unwrap_or_default().chain(Head::new(n)))
     }
-}
 
-impl<T, B: IntoBuf> fmt::Debug for FramedWrite<T, B>
-    where T: fmt::Debug,
-          B::Buf: fmt::Debug
-{
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.debug_struct("FramedWrite")
-            .field("inner", &self.inner)
-            .field("builder", &self.builder)
-            .field("frame", &self.frame)
-            .finish()
+    // Build the complete frame for `buf`, without copying the payload
+    fn encode_frame<B: Buf>(&self, buf: B) -> Result<EncodedFrame<B>, NetstringError> {
+        self.encode_prefixed(self.builder.write_prefix.clone(), buf)
     }
-}
 
-// ===== impl Builder =====
+    // Build the complete frame for `buf`, with the given prefix
+    fn encode_prefixed<B: Buf>(&self, prefix: Option<Bytes>, buf: B) -> Result<EncodedFrame<B>, NetstringError> {
+        let head = self.encode_head(prefix, buf.remaining())?;
+        Ok(head.chain(buf).chain(NETSTRING_TAIL))
+    }
 
-impl Builder {
+    /// Encodes `payload` as a frame into any `BufMut`.
+    ///
+    /// This is the runtime independent counterpart of the `Encoder`
+    /// implementation, writing to a `Vec<u8>`, a `&mut [u8]` or any other
+    /// buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `dst` does not have enough remaining capacity for the
+    /// frame, like `BufMut::put`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokio_netstring::NetstringEncoder;
+    ///
+    /// let mut dst = Vec::new();
+    /// NetstringEncoder::new().encode_buf(&b"hello"[..], &mut dst).unwrap();
+    /// assert_eq!(dst, b"5:hello,");
+    /// ```
+    pub fn encode_buf<B: Buf, M: BufMut>(&self, payload: B, dst: &mut M) -> Result<(), NetstringError> {
+        let frame = self.encode_frame(payload)?;
+        dst.put(frame);
+
+        Ok(())
+    }
+}
+
+impl Default for NetstringEncoder {
+    fn default() -> NetstringEncoder {
+        NetstringEncoder::new()
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<B: Buf> codec::Encoder<B> for NetstringEncoder {
+    type Error = NetstringError;
+
+    fn encode(&mut self, item: B, dst: &mut BytesMut) -> Result<(), NetstringError> {
+        let frame = self.encode_frame(item)?;
+
+        dst.reserve(frame.remaining());
+        dst.put(frame);
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<B: Buf> codec::Encoder<Prefixed<B>> for NetstringEncoder {
+    type Error = NetstringError;
+
+    fn encode(&mut self, item: Prefixed<B>, dst: &mut BytesMut) -> Result<(), NetstringError> {
+        let (prefix, payload) = item.into_parts();
+        let frame = self.encode_prefixed(Some(prefix), payload)?;
+
+        dst.reserve(frame.remaining());
+        dst.put(frame);
+
+        Ok(())
+    }
+}
+
+// ===== impl FramedWrite =====
+
+#[cfg(feature = "tokio")]
+impl<T: AsyncWrite, B: Buf> FramedWrite<T, B> {
+    /// Creates a new `FramedWrite` with default configuration values.
+    pub fn new(inner: T) -> FramedWrite<T, B> {
+        Builder::new().new_write(inner)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T, B> FramedWrite<T, B> {
+    /// Returns a reference to the underlying I/O stream wrapped by
+    /// `FramedWrite`.
+    ///
+    /// Note that care should be taken to not tamper with the underlying stream
+    /// of data coming in as it may corrupt the stream of frames otherwise
+    /// being worked with.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying I/O stream wrapped by
+    /// `FramedWrite`.
+    ///
+    /// Note that care should be taken to not tamper with the underlying stream
+    /// of data coming in as it may corrupt the stream of frames otherwise being
+    /// worked with.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Consumes the `FramedWrite`, returning its underlying I/O stream.
+    ///
+    /// Note that care should be taken to not tamper with the underlying stream
+    /// of data coming in as it may corrupt the stream of frames otherwise being
+    /// worked with.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T: AsyncWrite, B: Buf> FramedWrite<T, B> {
+    // Write queued frames to `T`. Writes everything when `all` is set,
+    // otherwise stops as soon as the queue is back under the high-water mark.
+    fn do_write(self: Pin<&mut Self>, cx: &mut Context<'_>, all: bool) -> Poll<Result<(), NetstringError>> {
+        let mut this = self.project();
+        let builder = &this.encoder.builder;
+
+        if *this.poisoned {
+            return Poll::Ready(Err(poisoned()));
+        }
+
+        while !this.frames.is_empty() {
+            let full = *this.queued >= builder.write_buffer_bytes ||
+                       this.frames.len() >= builder.max_queued_frames ||
+                       this.charge.budget().is_some_and(|budget| budget.available() == 0);
+
+            if !all && !full {
+                break;
+            }
+
+            // Hand as many queued frames as possible to the transport in a
+            // single vectored write
+            let mut slices = [IoSlice::new(&[]); MAX_WRITE_SLICES];
+            let mut cnt = 0;
+
+            for frame in this.frames.iter() {
+                cnt += frame.chunks_vectored(&mut slices[cnt..]);
+
+                if cnt == MAX_WRITE_SLICES {
+                    break;
+                }
+            }
+
+            let mut n = ready!(this.inner.as_mut().poll_write_vectored(cx, &slices[..cnt]))?;
+
+            if n == 0 {
+                let err = io::Error::new(io::ErrorKind::WriteZero, "failed to write frame to transport");
+                return Poll::Ready(Err(err.into()));
+            }
+
+            // The uncharged frame is the last one, so it is written last
+            let charged = n.min(*this.queued - *this.uncharged);
+            this.charge.release(charged);
+            *this.uncharged -= n - charged;
+            *this.queued -= n;
+
+            // Drop the frames that were written completely
+            while n > 0 {
+                let frame = this.frames.front_mut().expect("wrote more than was queued");
+                let cnt = n.min(frame.remaining());
+
+                frame.advance(cnt);
+                n -= cnt;
+
+                if !frame.has_remaining() {
+                    this.frames.pop_front();
+                }
+            }
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    fn queue_frame(self: Pin<&mut Self>, buf: B) -> Result<(), NetstringError> {
+        let this = self.project();
+
+        if *this.poisoned {
+            return Err(poisoned());
+        }
+
+        let frame = this.encoder.encode_frame(buf)?;
+        let len = frame.remaining();
+
+        // A frame too large for what is left of the budget is still
+        // accepted, but written out before any other frame
+        if !this.charge.try_acquire(len) {
+            *this.uncharged = len;
+        }
+
+        *this.queued += len;
+        this.frames.push_back(frame);
+
+        Ok(())
+    }
+}
+
+// The error returned once a frame body was left incomplete on the transport
+#[cfg(feature = "tokio")]
+fn poisoned() -> NetstringError {
+    io::Error::other("transport left in the middle of an incomplete frame").into()
+}
+
+#[cfg(feature = "tokio")]
+impl<T: AsyncWrite, B: Buf> Sink<B> for FramedWrite<T, B> {
+    type Error = NetstringError;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), NetstringError>> {
+        // Only apply backpressure once the queue reaches the high-water mark,
+        // or while it holds a frame outside of the memory budget
+        let all = self.uncharged > 0;
+        ready!(self.as_mut().do_write(cx, all))?;
+
+        // Or once the memory budget is exhausted, with nothing left to release
+        match self.project().charge.budget() {
+            Some(budget) => budget.poll_available(cx).map(Ok),
+            None => Poll::Ready(Ok(())),
+        }
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: B) -> Result<(), NetstringError> {
+        self.queue_frame(item)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), NetstringError>> {
+        // Write all queued frames to T
+        ready!(self.as_mut().do_write(cx, true))?;
+
+        // Try flushing the underlying IO
+        self.project().inner.poll_flush(cx).map_err(NetstringError::from)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), NetstringError>> {
+        ready!(self.as_mut().poll_flush(cx))?;
+        self.project().inner.poll_shutdown(cx).map_err(NetstringError::from)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T: Stream, B> Stream for FramedWrite<T, B> {
+    type Item = T::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T::Item>> {
+        self.project().inner.poll_next(cx)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T: AsyncRead, B> AsyncRead for FramedWrite<T, B> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_read(cx, buf)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T, B> fmt::Debug for FramedWrite<T, B>
+    where T: fmt::Debug,
+          B: fmt::Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FramedWrite")
+            .field("inner", &self.inner)
+            .field("encoder", &self.encoder)
+            .field("frames", &self.frames)
+            .field("queued", &self.queued)
+            .field("uncharged", &self.uncharged)
+            .field("poisoned", &self.poisoned)
+            .field("charge", &self.charge)
+            .finish()
+    }
+}
+
+// ===== impl Builder =====
+
+impl Builder {
     /// Creates a new length delimited framer builder with default configuration
     /// values.
     ///
     /// # Examples
     ///
     /// ```
-    /// # extern crate tokio_io;
-    /// # extern crate tokio_netstring;
-    /// #
-    /// # use tokio_io::AsyncRead;
+    /// # use tokio::io::AsyncRead;
     /// use tokio_netstring::Builder;
     ///
+    /// # #[cfg(feature = "tokio")]
     /// # fn bind_read<T: AsyncRead>(io: T) {
     /// Builder::new()
     ///     .length_field_offset(0)
     ///     .strip_frame(true)
     ///     .new_read(io);
     /// # }
-    /// # pub fn main() {}
     /// ```
     pub fn new() -> Builder {
         Builder {
@@ -765,13 +1526,44 @@ impl Builder {
 
             // Default to strip the frame.
             strip_frame: true,
+
+            // Default to the number of digits in `u64::MAX`.
+            max_length_digits: 20,
+
+            // Default to strict length parsing.
+            lenient: false,
+
+            // Default to no prefix before the length field.
+            write_prefix: None,
+
+            // Default to queueing up to 8KB of outgoing frames.
+            write_buffer_bytes: 8 * 1_024,
+
+            // Default to as many frames as fit in a single vectored write.
+            max_queued_frames: MAX_WRITE_SLICES / 4,
+
+            // Default to growing the read buffer 8KB at a time.
+            reserve_strategy: ReserveStrategy::Incremental { chunk: 8 * 1_024 },
+
+            // Default to no memory budget.
+            #[cfg(feature = "tokio")]
+            memory_budget: None,
+
+            // Default to failing on oversized frames.
+            on_oversize: OversizePolicy::Error,
+
+            // Default to failing on corrupt frames.
+            resync: false,
+
+            // Default to the ',' ending netstrings.
+            terminators: NETSTRING_TAIL,
         }
     }
 
     /// Sets the max frame length
     ///
     /// This configuration option applies to both encoding and decoding. The
-    /// default value is 8MB.
+    /// default value is 32MB.
     ///
     /// When decoding, the length field read from the byte stream is checked
     /// against this setting **before** any adjustments are applied. When
@@ -781,18 +1573,15 @@ impl Builder {
     /// # Examples
     ///
     /// ```
-    /// # extern crate tokio_io;
-    /// # extern crate tokio_netstring;
-    /// #
-    /// # use tokio_io::AsyncRead;
+    /// # use tokio::io::AsyncRead;
     /// use tokio_netstring::Builder;
     ///
+    /// # #[cfg(feature = "tokio")]
     /// # fn bind_read<T: AsyncRead>(io: T) {
     /// Builder::new()
     ///     .max_frame_length(8 * 1024)
     ///     .new_read(io);
     /// # }
-    /// # pub fn main() {}
     /// ```
     pub fn max_frame_length(&mut self, val: usize) -> &mut Self {
         self.max_frame_len = val;
@@ -801,141 +1590,1030 @@ impl Builder {
 
     /// Sets the number of bytes in the header before the length field
     ///
-    /// This configuration option only applies to decoding.
+    /// This configuration option only applies to decoding. See
+    /// [`write_prefix`] for the encoding side.
+    ///
+    /// [`write_prefix`]: #method.write_prefix
     ///
     /// # Examples
     ///
     /// ```
-    /// # extern crate tokio_io;
-    /// # extern crate tokio_netstring;
-    /// #
-    /// # use tokio_io::AsyncRead;
+    /// # use tokio::io::AsyncRead;
     /// use tokio_netstring::Builder;
     ///
+    /// # #[cfg(feature = "tokio")]
     /// # fn bind_read<T: AsyncRead>(io: T) {
     /// Builder::new()
     ///     .length_field_offset(1)
     ///     .new_read(io);
     /// # }
-    /// # pub fn main() {}
     /// ```
     pub fn length_field_offset(&mut self, val: usize) -> &mut Self {
         self.length_field_offset = val;
         self
     }
 
-    /// Sets wether or not to streip the frame and leave only the payload
+    /// Sets how the read buffer grows to hold an incoming frame
     ///
-    /// Default value is `true`
+    /// Default value is `ReserveStrategy::Incremental { chunk: 8192 }`.
     ///
-    /// This configuration option only applies to decoding
+    /// This configuration option only applies to decoding. With
+    /// `ReserveStrategy::Eager`, room for the whole frame is reserved as soon
+    /// as its length has been read. This saves reallocations for large
+    /// frames, but a peer declaring a large frame and then stalling holds
+    /// on to that memory. With `ReserveStrategy::Incremental`, the buffer
+    /// only grows by `chunk` bytes at a time as the payload arrives.
+    ///
+    /// With either strategy, the buffer is released after a frame larger
+    /// than 64KB instead of being kept around for the next frames.
     ///
     /// # Examples
     ///
     /// ```
-    /// # extern crate tokio_io;
-    /// # extern crate tokio_netstring;
-    /// #
-    /// # use tokio_io::AsyncRead;
-    /// use tokio_netstring::Builder;
+    /// # use tokio::io::AsyncRead;
+    /// use tokio_netstring::{Builder, ReserveStrategy};
     ///
+    /// # #[cfg(feature = "tokio")]
     /// # fn bind_read<T: AsyncRead>(io: T) {
     /// Builder::new()
-    ///     .strip_frame(false)
+    ///     .reserve_strategy(ReserveStrategy::Incremental { chunk: 64 * 1024 })
     ///     .new_read(io);
     /// # }
-    /// # pub fn main() {}
     /// ```
-    pub fn strip_frame(&mut self, val: bool) -> &mut Self {
-        self.strip_frame = val;
+    pub fn reserve_strategy(&mut self, val: ReserveStrategy) -> &mut Self {
+        self.reserve_strategy = val;
         self
     }
 
-    /// Create a configured length delimited `FramedRead`
+    /// Sets the memory budget shared with other framers
+    ///
+    /// Default value is no budget.
+    ///
+    /// This configuration option applies to both encoding and decoding. The
+    /// read buffer of every `FramedRead` and the write queue of every
+    /// `FramedWrite` created from this `Builder` reserve their memory from
+    /// `val`. Once the budget is exhausted, `FramedRead` waits for memory to
+    /// be released before reading from its transport, and `FramedWrite`
+    /// writes out its queue and then waits before accepting more frames.
+    /// Frames are released from the budget once they leave the read buffer
+    /// or the write queue. Frames read larger than the whole budget fail
+    /// with `NetstringError::FrameTooLarge`, while a frame written larger
+    /// than what is left of the budget is written out before `FramedWrite`
+    /// accepts another one.
+    ///
+    /// The read buffer is grown in steps, each acquired from the budget
+    /// before it is allocated, so it never holds more than the budget
+    /// allows. The reserve strategy is not used with a budget.
     ///
     /// # Examples
     ///
     /// ```
-    /// # extern crate tokio_io;
-    /// # extern crate tokio_netstring;
-    /// #
-    /// # use tokio_io::AsyncRead;
-    /// use tokio_netstring::Builder;
+    /// # use tokio::io::AsyncRead;
+    /// use tokio_netstring::{Builder, MemoryBudget};
     ///
-    /// # fn bind_read<T: AsyncRead>(io: T) {
-    /// Builder::new()
-    ///     .length_field_offset(0)
-    ///     .strip_frame(false)
+    /// # fn bind_reads<T: AsyncRead>(a: T, b: T) {
+    /// let mut builder = Builder::new();
+    /// builder.memory_budget(MemoryBudget::new(64 * 1024 * 1024));
+    ///
+    /// // Both framers reserve from the same 64MB
+    /// builder.new_read(a);
+    /// builder.new_read(b);
+    /// # }
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub fn memory_budget(&mut self, val: MemoryBudget) -> &mut Self {
+        self.memory_budget = Some(val);
+        self
+    }
+
+    /// Sets what to do with frames over the max frame length
+    ///
+    /// Default value is `OversizePolicy::Error`.
+    ///
+    /// This configuration option only applies to decoding. With
+    /// `OversizePolicy::Error`, an oversized frame fails the stream with
+    /// `NetstringError::FrameTooLarge`. With `OversizePolicy::Skip`, its
+    /// payload and trailing `','` are discarded as they arrive, without being
+    /// buffered, and decoding resumes with the next frame. With
+    /// `OversizePolicy::Stream`, its payload is handed over in chunks as it
+    /// arrives.
+    ///
+    /// Skipped frames and chunks are only reported by the `EventDecoder` and
+    /// the `FramedRead` returned by [`new_event_read`]. Other decoders and
+    /// the [`parse_iter`] iterators drop skipped frames, and fail with
+    /// `NetstringError::FrameTooLarge` rather than drop the payload of a
+    /// frame to stream.
+    ///
+    /// [`new_event_read`]: #method.new_event_read
+    /// [`parse_iter`]: fn.parse_iter.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "tokio")]
+    /// use tokio_netstring::{Builder, Event, OversizePolicy};
+    /// use futures::TryStreamExt;
+    ///
+    /// # #[cfg(feature = "tokio")]
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() -> Result<(), tokio_netstring::NetstringError> {
+    /// let io: &[u8] = b"11:hello world,5:hello,";
+    /// let mut events = Builder::new()
+    ///     .max_frame_length(5)
+    ///     .on_oversize(OversizePolicy::Skip)
+    ///     .new_event_read(io);
+    ///
+    /// assert_eq!(events.try_next().await?, Some(Event::Skipped { offset: 0, len: 11 }));
+    ///
+    /// match events.try_next().await? {
+    ///     Some(Event::Frame(frame)) => assert_eq!(frame.payload(), &b"hello"[..]),
+    ///     event => panic!("unexpected event {:?}", event),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "tokio"))]
+    /// # fn main() {}
+    /// ```
+    pub fn on_oversize(&mut self, val: OversizePolicy) -> &mut Self {
+        self.on_oversize = val;
+        self
+    }
+
+    /// Sets whether decoding resumes after a corrupt frame
+    ///
+    /// Default value is `false`.
+    ///
+    /// This configuration option only applies to decoding. When set, an
+    /// invalid length field, a frame over the max frame length or a missing
+    /// terminator does not end the stream. Instead, the decoder discards
+    /// bytes until the buffer starts with a valid head followed by a
+    /// correctly terminated payload, then reports the bytes discarded as
+    /// `NetstringError::Corrupt` and carries on with that frame. A
+    /// `FramedRead` keeps yielding frames after such an error.
+    ///
+    /// Note that a candidate head declaring a large length is only rejected
+    /// once enough bytes have been read to check its terminator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokio_netstring::{Builder, NetstringError};
+    /// use futures::StreamExt;
+    ///
+    /// # #[cfg(feature = "tokio")]
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let io: &[u8] = b"5:hello,3:garbled,5:world,";
+    /// let mut frames = Builder::new()
+    ///     .resync(true)
     ///     .new_read(io);
+    ///
+    /// assert_eq!(frames.next().await.unwrap().unwrap(), &b"hello"[..]);
+    ///
+    /// match frames.next().await.unwrap() {
+    ///     Err(NetstringError::Corrupt { offset: 8, skipped: 10 }) => {}
+    ///     res => panic!("unexpected item {:?}", res),
+    /// }
+    ///
+    /// assert_eq!(frames.next().await.unwrap().unwrap(), &b"world"[..]);
     /// # }
-    /// # pub fn main() {}
+    /// # #[cfg(not(feature = "tokio"))]
+    /// # fn main() {}
     /// ```
-    pub fn new_read<T>(&self, upstream: T) -> FramedRead<T>
-        where T: AsyncRead
-    {
-        FramedRead {
-            inner: codec::FramedRead::new(upstream,
-                                          Decoder {
-                                              builder: *self,
-                                              state: DecodeState::Head,
-                                          }),
-        }
+    pub fn resync(&mut self, val: bool) -> &mut Self {
+        self.resync = val;
+        self
     }
 
-    /// Create a configured length delimited `FramedWrite`
+    /// Sets the bytes written before the length field of every frame
+    ///
+    /// Default value is empty.
+    ///
+    /// This configuration option only applies to encoding. It is the
+    /// counterpart of [`length_field_offset`], so that a single `Builder`
+    /// describes both directions of a protocol with a fixed frame prefix.
+    /// The prefix is not counted in the netstring length. Frames can also be
+    /// given a prefix of their own, by encoding [`Prefixed`] payloads.
+    ///
+    /// The prefix must be `length_field_offset` bytes long, encoding fails
+    /// with `NetstringError::PrefixLength` otherwise. Without a prefix,
+    /// frames are written from their length field whatever the length field
+    /// offset.
+    ///
+    /// [`length_field_offset`]: #method.length_field_offset
+    /// [`Prefixed`]: struct.Prefixed.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokio_netstring::Builder;
+    /// use futures::{SinkExt, TryStreamExt};
+    ///
+    /// # #[cfg(feature = "tokio")]
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() -> Result<(), tokio_netstring::NetstringError> {
+    /// let mut builder = Builder::new();
+    /// builder
+    ///     .length_field_offset(1)
+    ///     .write_prefix(&b"\x01"[..]);
+    ///
+    /// let mut buf = Vec::new();
+    /// let mut writer = builder.new_write(&mut buf);
+    /// writer.send(&b"hello"[..]).await?;
+    /// assert_eq!(buf, b"\x015:hello,");
+    ///
+    /// let mut reader = builder.new_frame_read(&buf[..]);
+    /// let frame = reader.try_next().await?.unwrap();
+    /// assert_eq!(frame.prefix(), &b"\x01"[..]);
+    /// assert_eq!(frame.payload(), &b"hello"[..]);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "tokio"))]
+    /// # fn main() {}
+    /// ```
+    pub fn write_prefix(&mut self, val: impl Into<Bytes>) -> &mut Self {
+        self.write_prefix = Some(val.into());
+        self
+    }
+
+    /// Sets the number of bytes `FramedWrite` queues before applying
+    /// backpressure
+    ///
+    /// Default value is 8KB.
+    ///
+    /// This configuration option only applies to encoding. Frames sent to a
+    /// `FramedWrite` are queued and written in batches, using a single
+    /// vectored write for as many frames as possible. `poll_ready` only
+    /// waits for the transport once the queued frames reach this many bytes
+    /// or [`max_queued_frames`] frames.
+    ///
+    /// [`max_queued_frames`]: #method.max_queued_frames
     ///
     /// # Examples
     ///
     /// ```
-    /// # extern crate tokio_io;
-    /// # extern crate tokio_netstring;
-    /// # extern crate bytes;
-    /// #
-    /// # use tokio_io::AsyncWrite;
+    /// # use tokio::io::AsyncWrite;
     /// # use tokio_netstring as netstring;
-    /// # use bytes::BytesMut;
+    /// # use bytes::Bytes;
+    /// # #[cfg(feature = "tokio")]
     /// # fn write_frame<T: AsyncWrite>(io: T) {
-    /// # let _: netstring::FramedWrite<T, BytesMut> =
+    /// # let _: netstring::FramedWrite<T, Bytes> =
     /// netstring::Builder::new()
+    ///     .write_buffer_bytes(64 * 1024)
     ///     .new_write(io);
     /// # }
-    /// # pub fn main() {}
     /// ```
-    pub fn new_write<T, B>(&self, inner: T) -> FramedWrite<T, B>
-        where T: AsyncWrite,
-              B: IntoBuf
-    {
-        FramedWrite {
-            inner: inner,
-            builder: *self,
-            frame: None,
-        }
+    pub fn write_buffer_bytes(&mut self, val: usize) -> &mut Self {
+        self.write_buffer_bytes = val;
+        self
     }
 
-    /// Create a configured netstring delimited `Framed`
+    /// Sets the number of frames `FramedWrite` queues before applying
+    /// backpressure
+    ///
+    /// Default value is `16`.
+    ///
+    /// This configuration option only applies to encoding. See
+    /// [`write_buffer_bytes`] for details. A value of `1` writes every frame
+    /// before accepting the next one.
+    ///
+    /// [`write_buffer_bytes`]: #method.write_buffer_bytes
     ///
     /// # Examples
     ///
     /// ```
-    /// # extern crate tokio_io;
-    /// # extern crate tokio_netstring;
-    /// # extern crate bytes;
-    /// #
-    /// # use tokio_io::{AsyncRead, AsyncWrite};
+    /// # use tokio::io::AsyncWrite;
     /// # use tokio_netstring as netstring;
-    /// # use bytes::BytesMut;
-    /// # fn write_frame<T: AsyncRead + AsyncWrite>(io: T) {
-    /// # let _: netstring::Framed<T, BytesMut> =
+    /// # use bytes::Bytes;
+    /// # #[cfg(feature = "tokio")]
+    /// # fn write_frame<T: AsyncWrite>(io: T) {
+    /// # let _: netstring::FramedWrite<T, Bytes> =
     /// netstring::Builder::new()
-    ///     .new_framed(io);
+    ///     .max_queued_frames(64)
+    ///     .new_write(io);
     /// # }
-    /// # pub fn main() {}
     /// ```
-    pub fn new_framed<T, B>(&self, inner: T) -> Framed<T, B>
-        where T: AsyncRead + AsyncWrite,
-              B: IntoBuf
-    {
-        let inner = self.new_read(self.new_write(inner));
-        Framed { inner: inner }
+    pub fn max_queued_frames(&mut self, val: usize) -> &mut Self {
+        self.max_queued_frames = val;
+        self
+    }
+
+    /// Sets whether or not to strip the frame and leave only the payload
+    ///
+    /// Default value is `true`
+    ///
+    /// This configuration option only applies to decoding
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::AsyncRead;
+    /// use tokio_netstring::Builder;
+    ///
+    /// # #[cfg(feature = "tokio")]
+    /// # fn bind_read<T: AsyncRead>(io: T) {
+    /// Builder::new()
+    ///     .strip_frame(false)
+    ///     .new_read(io);
+    /// # }
+    /// ```
+    pub fn strip_frame(&mut self, val: bool) -> &mut Self {
+        self.strip_frame = val;
+        self
+    }
+
+    /// Sets the maximum number of bytes in the length field
+    ///
+    /// Default value is `20`, enough for any `u64` length.
+    ///
+    /// This configuration option only applies to decoding. A length field
+    /// that grows past this bound without reaching the `':'` is rejected
+    /// without waiting for the rest of the frame.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::AsyncRead;
+    /// use tokio_netstring::Builder;
+    ///
+    /// # #[cfg(feature = "tokio")]
+    /// # fn bind_read<T: AsyncRead>(io: T) {
+    /// Builder::new()
+    ///     .max_length_digits(4)
+    ///     .new_read(io);
+    /// # }
+    /// ```
+    pub fn max_length_digits(&mut self, val: usize) -> &mut Self {
+        self.max_length_digits = val;
+        self
+    }
+
+    /// Sets whether or not to accept sloppy length fields
+    ///
+    /// Default value is `false`
+    ///
+    /// This configuration option only applies to decoding. By default the
+    /// length field must be a non-empty run of ASCII digits without leading
+    /// zeros, except for a lone `0`, as required by the netstring spec. In
+    /// lenient mode leading zeros, a leading `'+'` and ASCII whitespace
+    /// around the digits are accepted as well.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::AsyncRead;
+    /// use tokio_netstring::Builder;
+    ///
+    /// # #[cfg(feature = "tokio")]
+    /// # fn bind_read<T: AsyncRead>(io: T) {
+    /// Builder::new()
+    ///     .lenient(true)
+    ///     .new_read(io);
+    /// # }
+    /// ```
+    pub fn lenient(&mut self, val: bool) -> &mut Self {
+        self.lenient = val;
+        self
+    }
+
+    /// Create a configured `NetstringCodec`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// use tokio_netstring::Builder;
+    /// use tokio_util::codec::Framed;
+    ///
+    /// # fn bind_transport<T: AsyncRead + AsyncWrite>(io: T) {
+    /// let codec = Builder::new()
+    ///     .max_frame_length(8 * 1024)
+    ///     .new_codec();
+    ///
+    /// Framed::new(io, codec);
+    /// # }
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub fn new_codec(&self) -> NetstringCodec {
+        NetstringCodec {
+            decoder: self.new_decoder(),
+            encoder: self.new_encoder(),
+        }
+    }
+
+    /// Create a configured `NetstringDecoder`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::AsyncRead;
+    /// use tokio_netstring::Builder;
+    /// # #[cfg(feature = "tokio")]
+    /// use tokio_util::codec::FramedRead;
+    ///
+    /// # #[cfg(feature = "tokio")]
+    /// # fn bind_read<T: AsyncRead>(io: T) {
+    /// let decoder = Builder::new()
+    ///     .length_field_offset(1)
+    ///     .new_decoder();
+    ///
+    /// FramedRead::new(io, decoder);
+    /// # }
+    /// ```
+    pub fn new_decoder(&self) -> NetstringDecoder {
+        NetstringDecoder {
+            builder: self.clone(),
+            state: DecodeState::Parse(ParseState::default()),
+            offset: 0,
+            events: false,
+        }
+    }
+
+    /// Create a configured `NetstringEncoder`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::AsyncWrite;
+    /// use tokio_netstring::Builder;
+    /// # #[cfg(feature = "tokio")]
+    /// use tokio_util::codec::FramedWrite;
+    ///
+    /// # #[cfg(feature = "tokio")]
+    /// # fn bind_write<T: AsyncWrite>(io: T) {
+    /// let encoder = Builder::new()
+    ///     .max_frame_length(8 * 1024)
+    ///     .new_encoder();
+    ///
+    /// FramedWrite::new(io, encoder);
+    /// # }
+    /// ```
+    pub fn new_encoder(&self) -> NetstringEncoder {
+        NetstringEncoder {
+            builder: self.clone(),
+        }
+    }
+
+    /// Create a configured `Parser`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokio_netstring::{Builder, Status};
+    ///
+    /// let mut parser = Builder::new()
+    ///     .length_field_offset(1)
+    ///     .new_parser();
+    ///
+    /// assert!(matches!(parser.parse(b"#2:hi,"), Status::Frame(payload) if payload == (3..5)));
+    /// ```
+    pub fn new_parser(&self) -> Parser {
+        Parser::from_builder(self.clone())
+    }
+
+    /// Create a configured `ParseIter` over the frames of `buf`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokio_netstring::Builder;
+    ///
+    /// let mut frames = Builder::new()
+    ///     .strip_frame(false)
+    ///     .new_parse_iter(b"5:hello,");
+    ///
+    /// assert_eq!(frames.next().unwrap().unwrap(), b"5:hello,");
+    /// ```
+    pub fn new_parse_iter<'a>(&self, buf: &'a [u8]) -> ParseIter<'a> {
+        ParseIter::new(buf, self.clone())
+    }
+
+    /// Create a configured `ParseIterBytes` over the frames of `buf`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokio_netstring::Builder;
+    /// use bytes::Bytes;
+    ///
+    /// let mut frames = Builder::new()
+    ///     .max_frame_length(4)
+    ///     .new_parse_iter_bytes(Bytes::from_static(b"5:hello,"));
+    ///
+    /// assert!(frames.next().unwrap().is_err());
+    /// assert!(frames.next().is_none());
+    /// ```
+    pub fn new_parse_iter_bytes(&self, buf: Bytes) -> ParseIterBytes {
+        ParseIterBytes::new(buf, self.clone())
+    }
+
+    /// Create a configured `NestedReader` over the frames of `payload`
+    ///
+    /// The frames of `payload`, and of the payloads read with
+    /// `read_nested`, are validated with this configuration. No max frame
+    /// length or length field offset applies to them though, nested frames
+    /// are bounded by the payload holding them and carry no prefix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokio_netstring::Builder;
+    /// use bytes::Bytes;
+    ///
+    /// let mut fields = Builder::new()
+    ///     .max_length_digits(1)
+    ///     .new_nested_reader(Bytes::from_static(b"3:abc,10:0123456789,"));
+    ///
+    /// assert_eq!(fields.read().unwrap(), "abc");
+    /// assert!(fields.read().is_err());
+    /// ```
+    pub fn new_nested_reader<B: Into<Bytes>>(&self, payload: B) -> NestedReader {
+        NestedReader::with_builder(payload.into(), self)
+    }
+
+    /// Decode the netstring at the start of `buf` with this configuration
+    ///
+    /// This is the configured counterpart of [`decode_one`], returning the
+    /// payload along with the rest of `buf`.
+    ///
+    /// [`decode_one`]: fn.decode_one.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokio_netstring::Builder;
+    ///
+    /// let res = Builder::new()
+    ///     .max_frame_length(4)
+    ///     .decode_one(b"5:hello,");
+    ///
+    /// assert!(res.is_err());
+    /// ```
+    pub fn decode_one<'a>(&self, buf: &'a [u8]) -> Result<(&'a [u8], &'a [u8]), NetstringError> {
+        oneshot::decode_first(self.new_parse_iter(buf))
+    }
+
+    /// Create a configured length delimited `FramedRead`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::AsyncRead;
+    /// use tokio_netstring::Builder;
+    ///
+    /// # fn bind_read<T: AsyncRead>(io: T) {
+    /// Builder::new()
+    ///     .length_field_offset(0)
+    ///     .strip_frame(false)
+    ///     .new_read(io);
+    /// # }
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub fn new_read<T>(&self, upstream: T) -> FramedRead<T>
+        where T: AsyncRead
+    {
+        FramedRead::with_decoder(upstream, self.new_decoder(), self.memory_budget.clone())
+    }
+
+    /// Create a configured `FrameDecoder`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::AsyncRead;
+    /// use tokio_netstring::Builder;
+    /// use tokio_util::codec::FramedRead;
+    ///
+    /// # fn bind_read<T: AsyncRead>(io: T) {
+    /// let decoder = Builder::new()
+    ///     .length_field_offset(1)
+    ///     .new_frame_decoder();
+    ///
+    /// FramedRead::new(io, decoder);
+    /// # }
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub fn new_frame_decoder(&self) -> FrameDecoder {
+        FrameDecoder::from_decoder(self.new_decoder())
+    }
+
+    /// Create a configured `FramedRead` yielding [`Frame`] values
+    ///
+    /// Each [`Frame`] carries the prefix bytes skipped by
+    /// `length_field_offset`, the payload and the position of the frame in
+    /// the stream. The `strip_frame` setting does not apply.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokio_netstring::Builder;
+    /// use futures::TryStreamExt;
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() -> Result<(), tokio_netstring::NetstringError> {
+    /// let io: &[u8] = b"\x015:hello,\x025:world,";
+    /// let mut frames = Builder::new()
+    ///     .length_field_offset(1)
+    ///     .new_frame_read(io);
+    ///
+    /// let frame = frames.try_next().await?.unwrap();
+    /// assert_eq!(frame.prefix(), &b"\x01"[..]);
+    /// assert_eq!(frame.payload(), &b"hello"[..]);
+    /// assert_eq!(frame.offset(), 0);
+    ///
+    /// let frame = frames.try_next().await?.unwrap();
+    /// assert_eq!(frame.prefix(), &b"\x02"[..]);
+    /// assert_eq!(frame.offset(), 9);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub fn new_frame_read<T>(&self, upstream: T) -> FramedRead<T, FrameDecoder>
+        where T: AsyncRead
+    {
+        FramedRead::with_decoder(upstream, self.new_frame_decoder(), self.memory_budget.clone())
+    }
+
+    /// Create a configured `EventDecoder`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::AsyncRead;
+    /// use tokio_netstring::{Builder, OversizePolicy};
+    /// use tokio_util::codec::FramedRead;
+    ///
+    /// # fn bind_read<T: AsyncRead>(io: T) {
+    /// let decoder = Builder::new()
+    ///     .on_oversize(OversizePolicy::Stream)
+    ///     .new_event_decoder();
+    ///
+    /// FramedRead::new(io, decoder);
+    /// # }
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub fn new_event_decoder(&self) -> EventDecoder {
+        EventDecoder::from_decoder(self.new_decoder())
+    }
+
+    /// Create a configured `FramedRead` yielding [`Event`] values
+    ///
+    /// On top of the frames, the events report the oversized frames handled
+    /// according to [`on_oversize`].
+    ///
+    /// [`on_oversize`]: #method.on_oversize
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokio_netstring::{Builder, Event, OversizePolicy};
+    /// use futures::TryStreamExt;
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() -> Result<(), tokio_netstring::NetstringError> {
+    /// let io: &[u8] = b"11:hello world,";
+    /// let events: Vec<Event> = Builder::new()
+    ///     .max_frame_length(5)
+    ///     .on_oversize(OversizePolicy::Stream)
+    ///     .new_event_read(io)
+    ///     .try_collect()
+    ///     .await?;
+    ///
+    /// assert_eq!(events, vec![Event::Chunk {
+    ///     offset: 0,
+    ///     len: 11,
+    ///     data: "hello world".into(),
+    ///     last: true,
+    /// }]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub fn new_event_read<T>(&self, upstream: T) -> FramedRead<T, EventDecoder>
+        where T: AsyncRead
+    {
+        FramedRead::with_decoder(upstream, self.new_event_decoder(), self.memory_budget.clone())
+    }
+
+    /// Create a configured `TnetstringDecoder`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokio_netstring::Builder;
+    /// use tokio_netstring::tnetstring::Value;
+    /// use bytes::BytesMut;
+    ///
+    /// let mut decoder = Builder::new()
+    ///     .max_frame_length(1024)
+    ///     .new_tnetstring_decoder();
+    ///
+    /// let mut src = BytesMut::from(&b"0:~"[..]);
+    /// assert_eq!(decoder.decode_buf(&mut src).unwrap(), Some(Value::Null));
+    /// ```
+    pub fn new_tnetstring_decoder(&self) -> TnetstringDecoder {
+        TnetstringDecoder::from_builder(self)
+    }
+
+    /// Create a configured `FramedRead` yielding tnetstring [`Value`]s
+    ///
+    /// Frames end with a type tag in place of the `','`, and their payload
+    /// is parsed according to it. See the [`tnetstring`] module for more
+    /// detail.
+    ///
+    /// [`Value`]: tnetstring/enum.Value.html
+    /// [`tnetstring`]: tnetstring/index.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokio_netstring::Builder;
+    /// use tokio_netstring::tnetstring::Value;
+    /// use futures::TryStreamExt;
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() -> Result<(), tokio_netstring::NetstringError> {
+    /// let io: &[u8] = b"12:5:hello,1:1#]4:true!";
+    /// let values: Vec<Value> = Builder::new()
+    ///     .new_tnetstring_read(io)
+    ///     .try_collect()
+    ///     .await?;
+    ///
+    /// assert_eq!(values, vec![
+    ///     Value::List(vec![Value::from("hello"), Value::Integer(1)]),
+    ///     Value::Bool(true),
+    /// ]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub fn new_tnetstring_read<T>(&self, upstream: T) -> FramedRead<T, TnetstringDecoder>
+        where T: AsyncRead
+    {
+        FramedRead::with_decoder(upstream, self.new_tnetstring_decoder(), self.memory_budget.clone())
+    }
+
+    /// Create a configured `TnetstringEncoder`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokio_netstring::Builder;
+    /// use tokio_netstring::tnetstring::Value;
+    ///
+    /// let encoder = Builder::new()
+    ///     .length_field_offset(1)
+    ///     .write_prefix(&b"\x01"[..])
+    ///     .new_tnetstring_encoder();
+    ///
+    /// let mut dst = Vec::new();
+    /// encoder.encode_buf(&Value::from("hi"), &mut dst).unwrap();
+    /// assert_eq!(dst, b"\x012:hi,");
+    /// ```
+    pub fn new_tnetstring_encoder(&self) -> TnetstringEncoder {
+        TnetstringEncoder::from_builder(self)
+    }
+
+    /// Create a configured `BodyStream`, yielding a reader per frame
+    ///
+    /// Each [`Body`] reads the payload of one frame straight from the
+    /// transport, without buffering it whole. The `strip_frame` setting does
+    /// not apply.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokio_netstring::Builder;
+    /// use futures::TryStreamExt;
+    /// use tokio::io::AsyncReadExt;
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let io: &[u8] = b"5:hello,5:world,";
+    /// let mut bodies = Builder::new()
+    ///     .max_frame_length(1024 * 1024 * 1024)
+    ///     .new_body_read(io);
+    ///
+    /// let mut body = bodies.try_next().await?.unwrap();
+    /// assert_eq!(body.len(), 5);
+    ///
+    /// let mut payload = Vec::new();
+    /// body.read_to_end(&mut payload).await?;
+    /// assert_eq!(payload, b"hello");
+    ///
+    /// // Dropping a body skips the rest of its payload
+    /// drop(bodies.try_next().await?.unwrap());
+    /// assert!(bodies.try_next().await?.is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub fn new_body_read<T>(&self, upstream: T) -> BodyStream<T>
+        where T: AsyncRead
+    {
+        BodyStream::new(upstream, self.clone())
+    }
+
+    /// Create a configured blocking `sync::Reader`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokio_netstring::Builder;
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let mut reader = Builder::new()
+    ///     .max_frame_length(1024)
+    ///     .new_sync_read(&b"5:hello,"[..]);
+    ///
+    /// let mut frame = Vec::new();
+    /// assert!(reader.read_frame_into(&mut frame)?);
+    /// assert_eq!(frame, b"hello");
+    /// assert!(!reader.read_frame_into(&mut frame)?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn new_sync_read<R>(&self, inner: R) -> sync::Reader<R>
+        where R: io::Read
+    {
+        // The memory budget only applies to the async framers
+        #[cfg(feature = "tokio")]
+        let builder = Builder { memory_budget: None, ..self.clone() };
+        #[cfg(not(feature = "tokio"))]
+        let builder = self;
+
+        sync::Reader::with_decoder(inner, builder.new_decoder())
+    }
+
+    /// Create a configured blocking `sync::Writer`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokio_netstring::Builder;
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let mut writer = Builder::new()
+    ///     .length_field_offset(1)
+    ///     .write_prefix(&b"\x01"[..])
+    ///     .new_sync_write(Vec::new());
+    ///
+    /// writer.write_frame(b"hello")?;
+    /// assert_eq!(writer.get_ref(), b"\x015:hello,");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn new_sync_write<W>(&self, inner: W) -> sync::Writer<W>
+        where W: io::Write
+    {
+        sync::Writer::with_encoder(inner, self.new_encoder())
+    }
+
+    /// Create a configured length delimited `FramedWrite`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::AsyncWrite;
+    /// # use tokio_netstring as netstring;
+    /// # use bytes::BytesMut;
+    /// # fn write_frame<T: AsyncWrite>(io: T) {
+    /// # let _: netstring::FramedWrite<T, BytesMut> =
+    /// netstring::Builder::new()
+    ///     .new_write(io);
+    /// # }
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub fn new_write<T, B>(&self, inner: T) -> FramedWrite<T, B>
+        where T: AsyncWrite,
+              B: Buf
+    {
+        FramedWrite {
+            inner,
+            encoder: self.new_encoder(),
+            frames: VecDeque::new(),
+            queued: 0,
+            uncharged: 0,
+            poisoned: false,
+            charge: Charge::new(self.memory_budget.clone()),
+        }
+    }
+
+    /// Create a configured netstring delimited `Framed`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio::io::{AsyncRead, AsyncWrite};
+    /// # use tokio_netstring as netstring;
+    /// # use bytes::BytesMut;
+    /// # fn write_frame<T: AsyncRead + AsyncWrite>(io: T) {
+    /// # let _: netstring::Framed<T, BytesMut> =
+    /// netstring::Builder::new()
+    ///     .new_framed(io);
+    /// # }
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub fn new_framed<T, B>(&self, inner: T) -> Framed<T, B>
+        where T: AsyncRead + AsyncWrite,
+              B: Buf
+    {
+        let inner = self.new_read(self.new_write(inner));
+        Framed { inner }
+    }
+
+    /// Create a configured `FramedRead` over a `futures-io` reader
+    ///
+    /// This method is only available with the `futures-io` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures::io::AsyncRead;
+    /// use tokio_netstring::Builder;
+    ///
+    /// # fn bind_read<T: AsyncRead>(io: T) {
+    /// Builder::new()
+    ///     .max_frame_length(8 * 1024)
+    ///     .new_futures_read(io);
+    /// # }
+    /// ```
+    #[cfg(feature = "futures-io")]
+    pub fn new_futures_read<T>(&self, upstream: T) -> FramedRead<Compat<T>>
+        where T: futures_io::AsyncRead
+    {
+        self.new_read(upstream.compat())
+    }
+
+    /// Create a configured `FramedWrite` over a `futures-io` writer
+    ///
+    /// This method is only available with the `futures-io` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures::io::AsyncWrite;
+    /// # use tokio_netstring as netstring;
+    /// # use bytes::BytesMut;
+    /// # use tokio_util::compat::Compat;
+    /// # fn write_frame<T: AsyncWrite>(io: T) {
+    /// # let _: netstring::FramedWrite<Compat<T>, BytesMut> =
+    /// netstring::Builder::new()
+    ///     .new_futures_write(io);
+    /// # }
+    /// ```
+    #[cfg(feature = "futures-io")]
+    pub fn new_futures_write<T, B>(&self, inner: T) -> FramedWrite<Compat<T>, B>
+        where T: futures_io::AsyncWrite,
+              B: Buf
+    {
+        self.new_write(inner.compat_write())
+    }
+
+    /// Create a configured netstring delimited `Framed` over a `futures-io`
+    /// transport
+    ///
+    /// This method is only available with the `futures-io` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use futures::io::{AsyncRead, AsyncWrite};
+    /// # use tokio_netstring as netstring;
+    /// # use bytes::BytesMut;
+    /// # use tokio_util::compat::Compat;
+    /// # fn write_frame<T: AsyncRead + AsyncWrite>(io: T) {
+    /// # let _: netstring::Framed<Compat<T>, BytesMut> =
+    /// netstring::Builder::new()
+    ///     .new_futures_framed(io);
+    /// # }
+    /// ```
+    #[cfg(feature = "futures-io")]
+    pub fn new_futures_framed<T, B>(&self, inner: T) -> Framed<Compat<T>, B>
+        where T: futures_io::AsyncRead + futures_io::AsyncWrite,
+              B: Buf
+    {
+        self.new_framed(inner.compat())
+    }
+
+    // Make sure a prefix to encode is read back as such by a decoder
+    // configured the same way
+    fn check_prefix(&self, prefix: &[u8]) -> Result<(), NetstringError> {
+        if prefix.len() != self.length_field_offset {
+            return Err(NetstringError::PrefixLength {
+                len: prefix.len(),
+                expected: self.length_field_offset,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Builder {
+    fn default() -> Builder {
+        Builder::new()
     }
 }
@@ -0,0 +1,250 @@
+use crate::parser::Head;
+use crate::{encoded_len, Builder, NetstringError, ParseIterBytes, NETSTRING_TAIL};
+
+use alloc::vec::Vec;
+
+use bytes::{BufMut, Bytes, BytesMut};
+
+/// Composes nested netstrings, such as lists and records.
+///
+/// Frames are added with [`push`], and nested frames are opened and closed
+/// with [`begin`] and [`end`]. The writer only records the structure and
+/// the lengths as they go, the frames are written out in a single pass by
+/// [`finish`] or [`write_to`]: every payload is copied once, whatever its
+/// depth, and the heads of the nested frames are never moved around.
+///
+/// No max frame length applies, like [`encode`].
+///
+/// # Examples
+///
+/// ```
+/// use tokio_netstring::NestedWriter;
+///
+/// let mut writer = NestedWriter::new();
+/// writer.push(&b"call"[..]);
+/// writer.begin();
+/// writer.push(&b"a"[..]);
+/// writer.push(&b"bc"[..]);
+/// writer.end();
+///
+/// assert_eq!(writer.finish(), &b"4:call,9:1:a,2:bc,,"[..]);
+/// ```
+///
+/// [`push`]: #method.push
+/// [`begin`]: #method.begin
+/// [`end`]: #method.end
+/// [`finish`]: #method.finish
+/// [`write_to`]: #method.write_to
+/// [`encode`]: fn.encode.html
+#[derive(Debug, Clone, Default)]
+pub struct NestedWriter {
+    // Frames in writing order
+    items: Vec<Item>,
+
+    // Nested frames not closed yet, innermost last, as the index of their
+    // `Item::Open` and the length of their payload so far
+    open: Vec<(usize, usize)>,
+
+    // Length of the top level frames
+    len: usize,
+}
+
+/// Walks the frames of a nested netstring payload.
+///
+/// `NestedReader` is an `Iterator` over the frames of a payload, yielding
+/// them as `Bytes` values sharing its memory. Whether a frame is itself
+/// made of frames is up to the protocol, [`read_nested`] walks one level
+/// down. Frames are validated like [`NetstringDecoder`] does with default
+/// configuration values, except that no max frame length applies: nested
+/// frames are bounded by the payload holding them. Use
+/// [`Builder::new_nested_reader`] for other configurations.
+///
+/// # Examples
+///
+/// ```
+/// use tokio_netstring::NestedReader;
+/// use bytes::BytesMut;
+///
+/// # fn main() -> Result<(), tokio_netstring::NetstringError> {
+/// // As yielded by a `FramedRead`
+/// let frame = BytesMut::from(&b"4:call,9:1:a,2:bc,,"[..]);
+/// let mut fields = NestedReader::new(frame);
+///
+/// assert_eq!(fields.read()?, "call");
+///
+/// let args = fields.read_nested()?.collect::<Result<Vec<_>, _>>()?;
+/// assert_eq!(args, ["a", "bc"]);
+/// assert!(fields.is_empty());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`read_nested`]: #method.read_nested
+/// [`NetstringDecoder`]: struct.NetstringDecoder.html
+/// [`Builder::new_nested_reader`]: struct.Builder.html#method.new_nested_reader
+#[derive(Debug, Clone)]
+pub struct NestedReader {
+    frames: ParseIterBytes,
+}
+
+// The configuration of `builder` for reading the frames nested in a
+// payload. They are bounded by the payload holding them, carry no prefix and
+// are yielded without their head.
+pub(crate) fn nested_builder(builder: &Builder) -> Builder {
+    let mut nested = builder.clone();
+    nested.max_frame_len = usize::MAX;
+    nested.length_field_offset = 0;
+    nested.strip_frame = true;
+    nested
+}
+
+#[derive(Debug, Clone)]
+enum Item {
+    // A frame, with its payload
+    Frame(Bytes),
+    // The head of a nested frame, with its payload length
+    Open(usize),
+    // The end of a nested frame
+    Close,
+}
+
+// ===== impl NestedWriter =====
+
+impl NestedWriter {
+    /// Creates a new, empty `NestedWriter`.
+    pub fn new() -> NestedWriter {
+        NestedWriter::default()
+    }
+
+    /// Adds a frame carrying `payload`.
+    ///
+    /// `payload` is kept as is until the frames are written out, `Bytes`
+    /// and `Vec<u8>` values are not copied.
+    pub fn push<B: Into<Bytes>>(&mut self, payload: B) -> &mut Self {
+        let payload = payload.into();
+
+        self.add(encoded_len(payload.len()));
+        self.items.push(Item::Frame(payload));
+        self
+    }
+
+    /// Opens a nested frame, the next frames go into its payload.
+    pub fn begin(&mut self) -> &mut Self {
+        self.open.push((self.items.len(), 0));
+        self.items.push(Item::Open(0));
+        self
+    }
+
+    /// Closes the innermost nested frame.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no nested frame is open.
+    pub fn end(&mut self) -> &mut Self {
+        let (index, len) = self.open.pop().expect("no nested frame to end");
+
+        self.items[index] = Item::Open(len);
+        self.items.push(Item::Close);
+        self.add(encoded_len(len));
+        self
+    }
+
+    /// Returns the number of bytes the frames take once written out.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a nested frame is still open.
+    pub fn encoded_len(&self) -> usize {
+        assert!(self.open.is_empty(), "nested frame left open");
+        self.len
+    }
+
+    /// Writes the frames out into a new buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a nested frame is still open.
+    pub fn finish(self) -> BytesMut {
+        let mut dst = BytesMut::with_capacity(self.encoded_len());
+        self.write_to(&mut dst);
+        dst
+    }
+
+    /// Writes the frames out into any `BufMut`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a nested frame is still open, or if `dst` does not have
+    /// [`encoded_len`] bytes of remaining capacity.
+    ///
+    /// [`encoded_len`]: #method.encoded_len
+    pub fn write_to<M: BufMut>(&self, dst: &mut M) {
+        assert!(self.open.is_empty(), "nested frame left open");
+
+        for item in &self.items {
+            match *item {
+                Item::Frame(ref payload) => {
+                    dst.put(Head::new(payload.len()));
+                    dst.put_slice(payload);
+                    dst.put_slice(NETSTRING_TAIL);
+                }
+                Item::Open(len) => dst.put(Head::new(len)),
+                Item::Close => dst.put_slice(NETSTRING_TAIL),
+            }
+        }
+    }
+
+    // Account for a frame of `len` bytes in the innermost open frame
+    fn add(&mut self, len: usize) {
+        match self.open.last_mut() {
+            Some(&mut (_, ref mut open)) => *open += len,
+            None => self.len += len,
+        }
+    }
+}
+
+// ===== impl NestedReader =====
+
+impl NestedReader {
+    /// Creates a new `NestedReader` over the frames of `payload`.
+    pub fn new<B: Into<Bytes>>(payload: B) -> NestedReader {
+        Builder::new().new_nested_reader(payload)
+    }
+
+    pub(crate) fn with_builder(payload: Bytes, builder: &Builder) -> NestedReader {
+        NestedReader {
+            frames: ParseIterBytes::new(payload, nested_builder(builder)),
+        }
+    }
+
+    /// Reads the next frame.
+    ///
+    /// Running out of frames is reported as `NetstringError::UnexpectedEof`.
+    pub fn read(&mut self) -> Result<Bytes, NetstringError> {
+        match self.frames.next() {
+            Some(res) => res,
+            None => Err(NetstringError::UnexpectedEof { expected: None, buffered: 0 }),
+        }
+    }
+
+    /// Reads the next frame, walking the frames of its payload.
+    ///
+    /// The frames of the payload are read with the same configuration.
+    pub fn read_nested(&mut self) -> Result<NestedReader, NetstringError> {
+        let payload = self.read()?;
+        Ok(NestedReader::with_builder(payload, self.frames.builder()))
+    }
+
+    /// Returns `true` if there are no frames left.
+    pub fn is_empty(&self) -> bool {
+        self.frames.remainder().is_empty()
+    }
+}
+
+impl Iterator for NestedReader {
+    type Item = Result<Bytes, NetstringError>;
+
+    fn next(&mut self) -> Option<Result<Bytes, NetstringError>> {
+        self.frames.next()
+    }
+}
@@ -0,0 +1,110 @@
+use crate::parser::Head;
+use crate::{parse_iter, NetstringError, ParseIter, NETSTRING_TAIL};
+
+use alloc::vec::Vec;
+
+use bytes::BufMut;
+
+/// Encodes `payload` as a netstring.
+///
+/// Unlike [`NetstringEncoder`], no max frame length applies. The output is
+/// the same as the one of an encoder with default configuration values, for
+/// payloads up to the default max frame length of 32MB. Larger netstrings
+/// are only read back by [`Builder::decode_one`] and decoders configured
+/// with a larger [`max_frame_length`].
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(tokio_netstring::encode(b"hello"), b"5:hello,");
+/// ```
+///
+/// [`NetstringEncoder`]: struct.NetstringEncoder.html
+/// [`Builder::decode_one`]: struct.Builder.html#method.decode_one
+/// [`max_frame_length`]: struct.Builder.html#method.max_frame_length
+pub fn encode(payload: &[u8]) -> Vec<u8> {
+    let mut dst = Vec::with_capacity(encoded_len(payload.len()));
+    encode_into(payload, &mut dst);
+    dst
+}
+
+/// Encodes `payload` as a netstring into any `BufMut`.
+///
+/// Like [`encode`], no max frame length applies.
+///
+/// # Panics
+///
+/// Panics if `dst` does not have [`encoded_len`] bytes of remaining
+/// capacity, like `BufMut::put`.
+///
+/// # Examples
+///
+/// ```
+/// let mut buf = [0; tokio_netstring::encoded_len(5)];
+/// tokio_netstring::encode_into(b"hello", &mut &mut buf[..]);
+///
+/// assert_eq!(&buf, b"5:hello,");
+/// ```
+///
+/// [`encode`]: fn.encode.html
+/// [`encoded_len`]: fn.encoded_len.html
+pub fn encode_into<M: BufMut>(payload: &[u8], dst: &mut M) {
+    dst.put(Head::new(payload.len()));
+    dst.put_slice(payload);
+    dst.put_slice(NETSTRING_TAIL);
+}
+
+/// Decodes the netstring at the start of `buf`.
+///
+/// Returns its payload along with the rest of `buf`. Frames are validated
+/// like [`NetstringDecoder`] does with default configuration values, `buf`
+/// not starting with a complete frame is reported as
+/// `NetstringError::UnexpectedEof`.
+///
+/// The default max frame length of 32MB applies, larger frames are reported
+/// as `NetstringError::FrameTooLarge` even though [`encode`] writes them.
+/// [`Builder::decode_one`] decodes with other configuration values.
+///
+/// # Examples
+///
+/// ```
+/// let (payload, rest) = tokio_netstring::decode_one(b"5:hello,5:world,").unwrap();
+///
+/// assert_eq!(payload, b"hello");
+/// assert_eq!(rest, b"5:world,");
+/// ```
+///
+/// [`NetstringDecoder`]: struct.NetstringDecoder.html
+/// [`encode`]: fn.encode.html
+/// [`Builder::decode_one`]: struct.Builder.html#method.decode_one
+pub fn decode_one(buf: &[u8]) -> Result<(&[u8], &[u8]), NetstringError> {
+    decode_first(parse_iter(buf))
+}
+
+// Take the first frame out of `frames`, along with the bytes following it
+pub(crate) fn decode_first(mut frames: ParseIter<'_>) -> Result<(&[u8], &[u8]), NetstringError> {
+    match frames.next() {
+        Some(Ok(payload)) => Ok((payload, frames.remainder())),
+        Some(Err(e)) => Err(e),
+        None => Err(NetstringError::UnexpectedEof { expected: None, buffered: 0 }),
+    }
+}
+
+/// Returns the length of the netstring carrying `payload_len` bytes.
+///
+/// This is the number of bytes written by [`encode`] and [`encode_into`].
+/// It can be used in const contexts, to size buffers.
+///
+/// # Examples
+///
+/// ```
+/// const LEN: usize = tokio_netstring::encoded_len(11);
+///
+/// assert_eq!(LEN, "11:hello world,".len());
+/// ```
+///
+/// [`encode`]: fn.encode.html
+/// [`encode_into`]: fn.encode_into.html
+pub const fn encoded_len(payload_len: usize) -> usize {
+    Head::len_of(payload_len) + payload_len + NETSTRING_TAIL.len()
+}
@@ -0,0 +1,349 @@
+use crate::{Builder, NetstringError, OversizePolicy, MAX_HEAD_LEN};
+
+use bytes::Buf;
+
+use core::ops::Range;
+
+/// A netstring parser independent of any I/O or runtime.
+///
+/// `Parser` holds no buffer and does no I/O. It is handed the bytes of the
+/// stream received so far, starting at the first byte of a frame, and
+/// reports whether they hold a complete frame. Each call resumes where the
+/// previous one stopped, so bytes trickling in are only scanned once. Every
+/// decoder in this crate is built on it, so any front end built on
+/// `Parser` behaves the same way. It is configured through
+/// [`Builder::new_parser`].
+///
+/// # Examples
+///
+/// ```
+/// use tokio_netstring::{Parser, Status};
+///
+/// let mut parser = Parser::new();
+/// let input = b"5:hello,5:wor";
+///
+/// let payload = match parser.parse(input) {
+///     Status::Frame(payload) => payload,
+///     status => panic!("unexpected status {:?}", status),
+/// };
+/// assert_eq!(&input[payload.clone()], b"hello");
+///
+/// // The frame ends with the ',' following the payload
+/// let rest = &input[payload.end + 1..];
+/// assert!(matches!(parser.parse(rest), Status::NeedMore(3)));
+/// ```
+///
+/// [`Builder::new_parser`]: struct.Builder.html#method.new_parser
+#[derive(Debug, Clone)]
+pub struct Parser {
+    // Configuration values
+    builder: Builder,
+
+    // Parse state of the current frame
+    state: ParseState,
+}
+
+/// What [`Parser::parse`] found in its input.
+#[derive(Debug)]
+pub enum Status {
+    /// The frame is not complete, at least `n` more bytes are needed.
+    NeedMore(usize),
+
+    /// A complete frame, with its payload at the given range of the input.
+    ///
+    /// The frame ends with the `','` found right after the payload, it is
+    /// `range.end + 1` bytes long.
+    Frame(Range<usize>),
+
+    /// The head of a frame over the max frame length, `head` bytes long,
+    /// declaring `len` bytes of payload.
+    ///
+    /// This is only reported with `OversizePolicy::Skip` and
+    /// `OversizePolicy::Stream`. Going through the payload and its `','` is
+    /// left to the caller.
+    Oversize {
+        /// Number of bytes up to and including the `:`
+        head: usize,
+        /// The payload length declared by the frame head
+        len: u64,
+    },
+
+    /// The input is not a valid netstring.
+    Error(NetstringError),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum ParseState {
+    // Scanning the length field
+    Length(LengthParser),
+    // The head has been parsed. `head` is the number of bytes up to and
+    // including the `:` and `len` the declared payload length.
+    Data { head: usize, len: usize },
+}
+
+// Incremental parser for the length field of a frame head.
+//
+// The parser remembers how much of the length field it has scanned, so
+// bytes trickling in are only looked at once.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct LengthParser {
+    // Number of length field bytes already scanned
+    scanned: usize,
+
+    // Length accumulated from the digits scanned so far
+    value: u64,
+
+    // Number of digits scanned so far
+    digits: usize,
+
+    // A '+' was scanned, lenient mode only
+    signed: bool,
+
+    // Whitespace was scanned after the digits, lenient mode only
+    trailing: bool,
+}
+
+// The `len:` part of a frame head, formatted on the stack
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Head {
+    buf: [u8; MAX_HEAD_LEN],
+
+    // Position of the first unwritten byte
+    pos: usize,
+}
+
+// ===== impl Parser =====
+
+impl Parser {
+    /// Creates a new `Parser` with default configuration values.
+    pub fn new() -> Parser {
+        Builder::new().new_parser()
+    }
+
+    pub(crate) fn from_builder(builder: Builder) -> Parser {
+        Parser {
+            builder,
+            state: ParseState::default(),
+        }
+    }
+
+    /// Parses the frame at the start of `input`.
+    ///
+    /// `input` must start with the bytes given to the previous call, unless
+    /// that call returned something other than `Status::NeedMore`. The
+    /// parser is then ready for the next frame, and the caller is expected
+    /// to drop the bytes of the frame it just reported.
+    pub fn parse(&mut self, input: &[u8]) -> Status {
+        self.state.parse(&self.builder, input)
+    }
+
+    /// Forgets about the frame being parsed, to start over with a new one.
+    pub fn reset(&mut self) {
+        self.state = ParseState::default();
+    }
+}
+
+impl Default for Parser {
+    fn default() -> Parser {
+        Parser::new()
+    }
+}
+
+// ===== impl ParseState =====
+
+impl ParseState {
+    pub(crate) fn parse(&mut self, builder: &Builder, input: &[u8]) -> Status {
+        match self.parse_frame(builder, input) {
+            Ok(status) => status,
+            Err(e) => {
+                *self = ParseState::default();
+                Status::Error(e)
+            }
+        }
+    }
+
+    fn parse_frame(&mut self, builder: &Builder, input: &[u8]) -> Result<Status, NetstringError> {
+        let (head, len) = match *self {
+            ParseState::Length(ref mut length) => {
+                if input.len() <= builder.length_field_offset {
+                    // Not enough data
+                    return Ok(Status::NeedMore(builder.length_field_offset + 1 - input.len()));
+                }
+
+                // Skip the required bytes and parse up to the `:` delimiting
+                // the end of the length
+                let (n, i) = match length.parse(builder, &input[builder.length_field_offset..])? {
+                    Some(v) => v,
+                    None => return Ok(Status::NeedMore(1)),
+                };
+
+                // | length_field_offset | netstring |':'| payload
+                let head = builder.length_field_offset + i + 1;
+
+                if n > builder.max_frame_len as u64 {
+                    *self = ParseState::default();
+                    return Ok(Status::Oversize { head, len: n });
+                }
+
+                // Note: `n` is within the max frame length
+                let len = n as usize;
+                *self = ParseState::Data { head, len };
+                (head, len)
+            }
+            ParseState::Data { head, len } => (head, len),
+        };
+
+        let end = head + len;
+
+        // Note: there is a ',' after the payload
+        if input.len() <= end {
+            return Ok(Status::NeedMore(end + 1 - input.len()));
+        }
+
+        // The payload must be followed by the ',', or one of the configured
+        // terminators
+        if !builder.terminators.contains(&input[end]) {
+            return Err(NetstringError::MissingTerminator { found: input[end], expected: builder.terminators });
+        }
+
+        *self = ParseState::default();
+
+        Ok(Status::Frame(head..end))
+    }
+
+    // The length of the frame being parsed, head and ',' included, once its
+    // head has been parsed
+    pub(crate) fn frame_len(&self) -> Option<usize> {
+        match *self {
+            ParseState::Data { head, len } => Some(head + len + 1),
+            ParseState::Length(..) => None,
+        }
+    }
+}
+
+impl Default for ParseState {
+    fn default() -> ParseState {
+        ParseState::Length(LengthParser::default())
+    }
+}
+
+// ===== impl LengthParser =====
+
+impl LengthParser {
+    // Parse the length field at the start of `field`, resuming where the
+    // previous call stopped. `field` must start with the bytes given to the
+    // previous calls.
+    //
+    // Returns the length and the position of the `:` terminating it, or
+    // `None` if the `:` has not arrived yet. Malformed input is rejected as
+    // soon as the offending byte is seen, and so are lengths over the max
+    // frame length unless they are to be skipped or streamed.
+    pub(crate) fn parse(&mut self, builder: &Builder, field: &[u8]) -> Result<Option<(u64, usize)>, NetstringError> {
+        // The length field up to and including the byte at `i` is invalid
+        let invalid = |i: usize| NetstringError::LengthParse {
+            offset: i,
+            bytes: field[..=i].to_vec(),
+        };
+
+        while self.scanned < field.len() {
+            let i = self.scanned;
+            let b = field[i];
+
+            if b == b':' {
+                if self.digits == 0 {
+                    return Err(invalid(i));
+                }
+
+                return Ok(Some((self.value, i)));
+            }
+
+            if i >= builder.max_length_digits {
+                return Err(invalid(i));
+            }
+
+            match b {
+                b'0'..=b'9' if !self.trailing => {
+                    if !builder.lenient && self.digits == 1 && self.value == 0 {
+                        return Err(invalid(i));
+                    }
+
+                    let declared = self.value.saturating_mul(10)
+                        .saturating_add((b - b'0') as u64);
+
+                    // A saturated length cannot be skipped either
+                    if declared == u64::MAX ||
+                       declared > builder.max_frame_len as u64 && builder.on_oversize == OversizePolicy::Error {
+                        return Err(NetstringError::FrameTooLarge {
+                            declared,
+                            max: builder.max_frame_len,
+                        });
+                    }
+
+                    self.value = declared;
+                    self.digits += 1;
+                }
+                b'+' if builder.lenient && self.digits == 0 && !self.signed => self.signed = true,
+                b if builder.lenient && b.is_ascii_whitespace() => {
+                    if self.digits > 0 {
+                        self.trailing = true;
+                    }
+                }
+                _ => return Err(invalid(i)),
+            }
+
+            self.scanned += 1;
+        }
+
+        Ok(None)
+    }
+}
+
+// ===== impl Head =====
+
+impl Head {
+    pub(crate) fn new(n: usize) -> Head {
+        let mut buf = [0; MAX_HEAD_LEN];
+        let pos = MAX_HEAD_LEN - Head::len_of(n);
+        let mut n = n;
+
+        buf[MAX_HEAD_LEN - 1] = b':';
+
+        // Write the digits right to left
+        for digit in buf[pos..MAX_HEAD_LEN - 1].iter_mut().rev() {
+            *digit = b'0' + (n % 10) as u8;
+            n /= 10;
+        }
+
+        Head { buf, pos }
+    }
+
+    // Number of bytes in the `len:` head of a frame carrying `n` bytes of
+    // payload
+    pub(crate) const fn len_of(n: usize) -> usize {
+        // The ':' and the first digit
+        let mut len = 2;
+        let mut n = n / 10;
+
+        while n > 0 {
+            len += 1;
+            n /= 10;
+        }
+
+        len
+    }
+}
+
+impl Buf for Head {
+    fn remaining(&self) -> usize {
+        MAX_HEAD_LEN - self.pos
+    }
+
+    fn chunk(&self) -> &[u8] {
+        &self.buf[self.pos..]
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        assert!(cnt <= self.remaining());
+        self.pos += cnt;
+    }
+}
@@ -0,0 +1,237 @@
+use crate::{FramedWrite, NetstringError, NETSTRING_TAIL, READ_CHUNK_LEN};
+
+use bytes::Buf;
+use futures_core::Stream;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use std::future::poll_fn;
+use std::pin::Pin;
+
+// ===== impl FramedWrite =====
+
+impl<T, B> FramedWrite<T, B>
+    where T: AsyncWrite + Unpin,
+          B: Buf
+{
+    /// Writes a frame of `len` bytes, read from `reader`.
+    ///
+    /// The frames queued beforehand are written first. The payload is then
+    /// copied from `reader` as it is read, without being buffered whole.
+    /// `reader` must hold exactly `len` bytes: it is read until EOF to make
+    /// sure it holds no more.
+    ///
+    /// If `reader` holds more, the frame is terminated after its first `len`
+    /// bytes and `NetstringError::BodyTooLong` is returned. If it ends early,
+    /// `NetstringError::BodyTooShort` is returned and the frame is left
+    /// incomplete, so every later write fails.
+    ///
+    /// `sendfile` is never used here, even when `reader` is a file. See
+    /// `send_file` for that on Linux.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokio_netstring::FramedWrite;
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() -> Result<(), tokio_netstring::NetstringError> {
+    /// let mut writer = FramedWrite::<_, &[u8]>::new(Vec::new());
+    ///
+    /// writer.send_reader(5, &b"hello"[..]).await?;
+    /// assert_eq!(writer.get_ref(), b"5:hello,");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn send_reader<R>(&mut self, len: u64, mut reader: R) -> Result<(), NetstringError>
+        where R: AsyncRead + Unpin
+    {
+        self.start_body(len).await?;
+
+        let mut chunk = vec![0; READ_CHUNK_LEN];
+        let mut remaining = len;
+
+        while remaining > 0 {
+            let max = remaining.min(chunk.len() as u64) as usize;
+            let n = reader.read(&mut chunk[..max]).await?;
+
+            if n == 0 {
+                return Err(NetstringError::BodyTooShort { declared: len, read: len - remaining });
+            }
+
+            self.inner.write_all(&chunk[..n]).await?;
+            remaining -= n as u64;
+        }
+
+        // The source must end along with the payload
+        let extra = reader.read(&mut chunk[..1]).await?;
+
+        self.end_body().await?;
+
+        if extra != 0 {
+            return Err(NetstringError::BodyTooLong { declared: len });
+        }
+
+        Ok(())
+    }
+
+    /// Writes a frame of `len` bytes, made of the chunks yielded by `stream`.
+    ///
+    /// The frames queued beforehand are written first. Each chunk is then
+    /// written as it is yielded. The chunks must add up to exactly `len`
+    /// bytes.
+    ///
+    /// If `stream` yields more, the frame is terminated after its first `len`
+    /// bytes and `NetstringError::BodyTooLong` is returned. If it ends early,
+    /// `NetstringError::BodyTooShort` is returned and the frame is left
+    /// incomplete, so every later write fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokio_netstring::FramedWrite;
+    /// use bytes::Bytes;
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() -> Result<(), tokio_netstring::NetstringError> {
+    /// let mut writer = FramedWrite::<_, Bytes>::new(Vec::new());
+    /// let chunks = futures::stream::iter(vec![Bytes::from("hello"), Bytes::from(" world")]);
+    ///
+    /// writer.send_stream(11, chunks).await?;
+    /// assert_eq!(writer.get_ref(), b"11:hello world,");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn send_stream<S, C>(&mut self, len: u64, mut stream: S) -> Result<(), NetstringError>
+        where S: Stream<Item = C> + Unpin,
+              C: Buf
+    {
+        self.start_body(len).await?;
+
+        let mut written = 0;
+
+        while let Some(mut chunk) = poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await {
+            if chunk.remaining() as u64 > len - written {
+                // Complete the frame with what fits of the chunk
+                let mut rest = chunk.take((len - written) as usize);
+                self.inner.write_all_buf(&mut rest).await?;
+                self.end_body().await?;
+
+                return Err(NetstringError::BodyTooLong { declared: len });
+            }
+
+            written += chunk.remaining() as u64;
+            self.inner.write_all_buf(&mut chunk).await?;
+        }
+
+        if written < len {
+            return Err(NetstringError::BodyTooShort { declared: len, read: written });
+        }
+
+        self.end_body().await
+    }
+
+    // Write out the queued frames, followed by the head of a frame of `len`
+    // bytes
+    pub(crate) async fn start_body(&mut self, len: u64) -> Result<(), NetstringError> {
+        poll_fn(|cx| Pin::new(&mut *self).do_write(cx, true)).await?;
+
+        let max = self.encoder.builder.max_frame_len;
+        let n = usize::try_from(len).map_err(|_| NetstringError::FrameTooLarge { declared: len, max })?;
+        let mut head = self.encoder.encode_head(self.encoder.builder.write_prefix.clone(), n)?;
+
+        // Until the terminator is written, the transport is mid-frame
+        self.poisoned = true;
+        self.inner.write_all_buf(&mut head).await?;
+
+        Ok(())
+    }
+
+    // Terminate the frame once its payload has been written
+    pub(crate) async fn end_body(&mut self) -> Result<(), NetstringError> {
+        self.inner.write_all(NETSTRING_TAIL).await?;
+        self.poisoned = false;
+        self.inner.flush().await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use crate::{FramedWrite, NetstringError};
+
+    use bytes::Buf;
+    use tokio::io::Interest;
+    use tokio::net::TcpStream;
+
+    use std::fs::File;
+    use std::io::{self, Seek};
+    use std::os::unix::io::AsRawFd;
+    use std::ptr;
+
+    // Largest number of bytes transferred by a single `sendfile` call
+    const MAX_SENDFILE_LEN: u64 = 0x7fff_f000;
+
+    impl<B: Buf> FramedWrite<TcpStream, B> {
+        /// Writes a frame made of the rest of `file`, using `sendfile`.
+        ///
+        /// The frames queued beforehand are written first. The payload is
+        /// then handed from `file` to the socket by the kernel, without being
+        /// copied through user space. It starts at the current position of
+        /// `file`, which is moved to its end. `len` must be the number of
+        /// bytes left in `file`, or `NetstringError::BodyTooShort` or
+        /// `NetstringError::BodyTooLong` is returned before anything is
+        /// written.
+        /// If `file` is truncated while it is sent, `NetstringError::BodyTooShort`
+        /// is returned and the frame is left incomplete, so every later write
+        /// fails.
+        ///
+        /// Note that reading `file` may block the executor thread while the
+        /// data is fetched from disk.
+        ///
+        /// This method is only available on Linux.
+        pub async fn send_file(&mut self, len: u64, file: &File) -> Result<(), NetstringError> {
+            // Check the length up front, before anything is written
+            let mut pos = file;
+            let left = file.metadata()?.len().saturating_sub(pos.stream_position()?);
+
+            if left < len {
+                return Err(NetstringError::BodyTooShort { declared: len, read: left });
+            }
+
+            if left > len {
+                return Err(NetstringError::BodyTooLong { declared: len });
+            }
+
+            self.start_body(len).await?;
+
+            let mut sent = 0;
+
+            while sent < len {
+                let count = (len - sent).min(MAX_SENDFILE_LEN) as usize;
+
+                let res = self.inner.async_io(Interest::WRITABLE, || {
+                    // SAFETY: both file descriptors are valid for the duration
+                    // of the call, and a null offset uses the file position
+                    let n = unsafe {
+                        libc::sendfile(self.inner.as_raw_fd(), file.as_raw_fd(), ptr::null_mut(), count)
+                    };
+
+                    if n < 0 {
+                        Err(io::Error::last_os_error())
+                    } else {
+                        Ok(n as u64)
+                    }
+                }).await;
+
+                match res? {
+                    // The file was truncated in the meantime
+                    0 => return Err(NetstringError::BodyTooShort { declared: len, read: sent }),
+                    n => sent += n,
+                }
+            }
+
+            self.end_body().await
+        }
+    }
+}
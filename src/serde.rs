@@ -0,0 +1,813 @@
+//! A serde data format made of nested netstrings.
+//!
+//! Every value is encoded as a netstring:
+//!
+//! - Structs, tuples and sequences hold the netstrings of their elements, in
+//!   order. Struct field names are not encoded, fields are matched by
+//!   position.
+//! - Maps hold the netstrings of their keys and values, alternating.
+//! - Booleans, numbers and chars hold their textual representation, such as
+//!   `true`, `-12` or `1.5`. Strings and byte arrays are held as is.
+//! - `()` and `None` are empty lists, `Some(value)` is a list holding the
+//!   value.
+//! - Enum variants are a list holding the variant name, followed by the
+//!   value of the variant if it has one.
+//!
+//! [`to_bytes`] returns the payload of the netstring encoding a value, ready
+//! to be sent as a frame through a `FramedWrite`. [`from_bytes`] decodes
+//! the payload of a frame, such as the `BytesMut` values yielded by a
+//! `FramedRead`, borrowing strings and byte arrays from it.
+//!
+//! The format is not self-describing. Types deserialized through
+//! `deserialize_any` see every value as a string, or as bytes if it is not
+//! valid UTF-8.
+//!
+//! This module is only available with the `serde` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use serde::{Deserialize, Serialize};
+//! use tokio_netstring::serde::{from_bytes, to_bytes};
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct Call<'a> {
+//!     method: &'a str,
+//!     args: Vec<u32>,
+//! }
+//!
+//! # fn main() -> Result<(), tokio_netstring::serde::Error> {
+//! let payload = to_bytes(&Call { method: "add", args: vec![1, 22] })?;
+//! assert_eq!(payload, &b"3:add,9:1:1,2:22,,"[..]);
+//!
+//! let call: Call = from_bytes(&payload)?;
+//! assert_eq!(call, Call { method: "add", args: vec![1, 22] });
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`to_bytes`]: fn.to_bytes.html
+//! [`from_bytes`]: fn.from_bytes.html
+
+use crate::nested::nested_builder;
+use crate::{Builder, NestedWriter, NetstringError, ParseIter};
+
+use alloc::string::{String, ToString};
+
+use bytes::{Bytes, BytesMut};
+
+use ::serde::de::{self, DeserializeSeed, IntoDeserializer, Visitor};
+use ::serde::ser::{self, Serialize};
+
+use core::fmt::{self, Display, Write};
+use core::str::{self, FromStr};
+
+/// Serializes Rust values as nested netstrings.
+///
+/// A `Serializer` is meant to serialize a single value, see [`to_bytes`].
+///
+/// [`to_bytes`]: fn.to_bytes.html
+#[derive(Debug, Default)]
+pub struct Serializer {
+    // Frames written so far
+    writer: NestedWriter,
+
+    // The top level value, when it is a scalar: it is not framed
+    scalar: Option<Bytes>,
+
+    // Number of compound values being serialized
+    depth: usize,
+}
+
+/// Deserializes Rust values from nested netstrings.
+///
+/// A `Deserializer` reads the payload of the netstring encoding a single
+/// value, see [`from_bytes`].
+///
+/// [`from_bytes`]: fn.from_bytes.html
+#[derive(Debug, Clone, Copy)]
+pub struct Deserializer<'de> {
+    // Payload of the value
+    input: &'de [u8],
+}
+
+/// An error produced while serializing or deserializing values.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// An error reported by a `Serialize` or `Deserialize` implementation.
+    Message(String),
+
+    /// The payload is not made of valid netstrings.
+    Netstring(NetstringError),
+
+    /// The payload is not a valid representation of the expected value.
+    Invalid {
+        /// What was expected
+        expected: &'static str,
+    },
+
+    /// A list holds more elements than the value deserialized from it.
+    TrailingFrames,
+}
+
+// Frames of a compound value, handed to visitors
+struct Frames<'de> {
+    iter: ParseIter<'de>,
+}
+
+// Frames of an enum value, the variant name coming first
+struct Enum<'de> {
+    frames: Frames<'de>,
+}
+
+/// Serializes `value` into the payload of its netstring.
+///
+/// # Examples
+///
+/// ```
+/// use tokio_netstring::serde::to_bytes;
+///
+/// # fn main() -> Result<(), tokio_netstring::serde::Error> {
+/// assert_eq!(to_bytes(&42)?, &b"42"[..]);
+/// assert_eq!(to_bytes(&("a", true))?, &b"1:a,4:true,"[..]);
+/// # Ok(())
+/// # }
+/// ```
+pub fn to_bytes<T: ?Sized + Serialize>(value: &T) -> Result<BytesMut, Error> {
+    let mut serializer = Serializer::new();
+    value.serialize(&mut serializer)?;
+    Ok(serializer.into_bytes())
+}
+
+/// Deserializes a value from the payload of its netstring.
+///
+/// Strings and byte arrays can be borrowed from `input`.
+///
+/// # Examples
+///
+/// ```
+/// use tokio_netstring::serde::from_bytes;
+///
+/// # fn main() -> Result<(), tokio_netstring::serde::Error> {
+/// let (name, flag): (&str, bool) = from_bytes(b"1:a,4:true,")?;
+/// assert_eq!((name, flag), ("a", true));
+/// # Ok(())
+/// # }
+/// ```
+pub fn from_bytes<'de, T: de::Deserialize<'de>>(input: &'de [u8]) -> Result<T, Error> {
+    T::deserialize(Deserializer::from_bytes(input))
+}
+
+// ===== impl Serializer =====
+
+impl Serializer {
+    /// Creates a new `Serializer`.
+    pub fn new() -> Serializer {
+        Serializer::default()
+    }
+
+    /// Returns the payload of the netstring encoding the serialized value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value was not serialized to the end.
+    pub fn into_bytes(self) -> BytesMut {
+        match self.scalar {
+            Some(payload) => payload.into(),
+            None => self.writer.finish(),
+        }
+    }
+
+    // Add a scalar value, the top level value is not framed
+    fn scalar(&mut self, payload: Bytes) -> Result<(), Error> {
+        if self.depth == 0 {
+            self.scalar = Some(payload);
+        } else {
+            self.writer.push(payload);
+        }
+
+        Ok(())
+    }
+
+    // Add a scalar value as its textual representation
+    fn display<T: Display>(&mut self, value: T) -> Result<(), Error> {
+        let mut text = String::new();
+        write!(text, "{}", value).map_err(<Error as ser::Error>::custom)?;
+
+        self.scalar(text.into())
+    }
+
+    // Open a compound value, the top level value is not framed
+    fn begin(&mut self) {
+        if self.depth > 0 {
+            self.writer.begin();
+        }
+
+        self.depth += 1;
+    }
+
+    fn end(&mut self) {
+        self.depth -= 1;
+
+        if self.depth > 0 {
+            self.writer.end();
+        }
+    }
+
+    // Open an enum value with its variant name
+    fn begin_variant(&mut self, variant: &'static str) -> Result<(), Error> {
+        self.begin();
+        self.scalar(Bytes::from_static(variant.as_bytes()))
+    }
+}
+
+impl ser::Serializer for &mut Serializer {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    fn serialize_bool(self, v: bool) -> Result<(), Error> {
+        self.scalar(Bytes::from_static(if v { b"true" } else { b"false" }))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), Error> {
+        self.display(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), Error> {
+        self.display(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), Error> {
+        self.display(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), Error> {
+        self.display(v)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<(), Error> {
+        self.display(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), Error> {
+        self.display(v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), Error> {
+        self.display(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), Error> {
+        self.display(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), Error> {
+        self.display(v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<(), Error> {
+        self.display(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), Error> {
+        self.display(v)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), Error> {
+        self.display(v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), Error> {
+        self.display(v)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), Error> {
+        self.scalar(Bytes::copy_from_slice(v.as_bytes()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), Error> {
+        self.scalar(Bytes::copy_from_slice(v))
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<(), Error> {
+        self.begin();
+        value.serialize(&mut *self)?;
+        self.end();
+        Ok(())
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        self.begin();
+        self.end();
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<(), Error> {
+        self.begin_variant(variant)?;
+        self.end();
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _name: &'static str, value: &T) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(self, _name: &'static str, _index: u32,
+                                                        variant: &'static str, value: &T)
+        -> Result<(), Error>
+    {
+        self.begin_variant(variant)?;
+        value.serialize(&mut *self)?;
+        self.end();
+        Ok(())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self, Error> {
+        self.begin();
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self, Error> {
+        self.serialize_seq(None)
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self, Error> {
+        self.serialize_seq(None)
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _index: u32, variant: &'static str, _len: usize)
+        -> Result<Self, Error>
+    {
+        self.begin_variant(variant)?;
+        self.begin();
+        Ok(self)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self, Error> {
+        self.serialize_seq(None)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self, Error> {
+        self.serialize_seq(None)
+    }
+
+    fn serialize_struct_variant(self, name: &'static str, index: u32, variant: &'static str, len: usize)
+        -> Result<Self, Error>
+    {
+        self.serialize_tuple_variant(name, index, variant, len)
+    }
+}
+
+impl ser::SerializeSeq for &mut Serializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Serializer::end(self);
+        Ok(())
+    }
+}
+
+impl ser::SerializeTuple for &mut Serializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Serializer::end(self);
+        Ok(())
+    }
+}
+
+impl ser::SerializeTupleStruct for &mut Serializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Serializer::end(self);
+        Ok(())
+    }
+}
+
+impl ser::SerializeTupleVariant for &mut Serializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        // Close the fields, then the variant
+        Serializer::end(self);
+        Serializer::end(self);
+        Ok(())
+    }
+}
+
+impl ser::SerializeMap for &mut Serializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Error> {
+        key.serialize(&mut **self)
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Serializer::end(self);
+        Ok(())
+    }
+}
+
+impl ser::SerializeStruct for &mut Serializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, _key: &'static str, value: &T) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), Error> {
+        // Fields are matched by position, they cannot be left out
+        Err(Error::Message(alloc::format!("cannot skip field `{}`", key)))
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Serializer::end(self);
+        Ok(())
+    }
+}
+
+impl ser::SerializeStructVariant for &mut Serializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, _key: &'static str, value: &T) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), Error> {
+        // Fields are matched by position, they cannot be left out
+        Err(Error::Message(alloc::format!("cannot skip field `{}`", key)))
+    }
+
+    fn end(self) -> Result<(), Error> {
+        // Close the fields, then the variant
+        Serializer::end(self);
+        Serializer::end(self);
+        Ok(())
+    }
+}
+
+// ===== impl Deserializer =====
+
+impl<'de> Deserializer<'de> {
+    /// Creates a new `Deserializer` reading the payload of the netstring
+    /// encoding a value.
+    pub fn from_bytes(input: &'de [u8]) -> Deserializer<'de> {
+        Deserializer { input }
+    }
+
+    fn str(&self) -> Result<&'de str, Error> {
+        str::from_utf8(self.input).map_err(|_| Error::Invalid { expected: "a UTF-8 string" })
+    }
+
+    // Parse the textual representation of a scalar value
+    fn parse<T: FromStr>(&self, expected: &'static str) -> Result<T, Error> {
+        self.str()?.parse().map_err(|_| Error::Invalid { expected })
+    }
+
+    fn frames(&self) -> Frames<'de> {
+        Frames::new(self.input)
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match str::from_utf8(self.input) {
+            Ok(v) => visitor.visit_borrowed_str(v),
+            Err(_) => visitor.visit_borrowed_bytes(self.input),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.input {
+            b"true" => visitor.visit_bool(true),
+            b"false" => visitor.visit_bool(false),
+            _ => Err(Error::Invalid { expected: "a boolean" }),
+        }
+    }
+
+    fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_i8(self.parse("an i8")?)
+    }
+
+    fn deserialize_i16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_i16(self.parse("an i16")?)
+    }
+
+    fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_i32(self.parse("an i32")?)
+    }
+
+    fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_i64(self.parse("an i64")?)
+    }
+
+    fn deserialize_i128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_i128(self.parse("an i128")?)
+    }
+
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_u8(self.parse("a u8")?)
+    }
+
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_u16(self.parse("a u16")?)
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_u32(self.parse("a u32")?)
+    }
+
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_u64(self.parse("a u64")?)
+    }
+
+    fn deserialize_u128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_u128(self.parse("a u128")?)
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_f32(self.parse("an f32")?)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_f64(self.parse("an f64")?)
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_char(self.parse("a char")?)
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_borrowed_str(self.str()?)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_borrowed_bytes(self.input)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let mut frames = self.frames();
+
+        let value = match frames.next_frame()? {
+            Some(payload) => visitor.visit_some(Deserializer::from_bytes(payload))?,
+            None => visitor.visit_none::<Error>()?,
+        };
+
+        frames.finish()?;
+        Ok(value)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if !self.input.is_empty() {
+            return Err(Error::Invalid { expected: "an empty list" });
+        }
+
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V)
+        -> Result<V::Value, Error>
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let mut frames = self.frames();
+        let value = visitor.visit_seq(&mut frames)?;
+
+        frames.finish()?;
+        Ok(value)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize, visitor: V)
+        -> Result<V::Value, Error>
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let mut frames = self.frames();
+        let value = visitor.visit_map(&mut frames)?;
+
+        frames.finish()?;
+        Ok(value)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(self, _name: &'static str, _fields: &'static [&'static str], visitor: V)
+        -> Result<V::Value, Error>
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V)
+        -> Result<V::Value, Error>
+    {
+        visitor.visit_enum(Enum { frames: self.frames() })
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Deserializer<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+// ===== impl Frames =====
+
+impl<'de> Frames<'de> {
+    fn new(input: &'de [u8]) -> Frames<'de> {
+        // Read like a `NestedReader` does
+        Frames {
+            iter: nested_builder(&Builder::new()).new_parse_iter(input),
+        }
+    }
+
+    fn next_frame(&mut self) -> Result<Option<&'de [u8]>, Error> {
+        Ok(self.iter.next().transpose()?)
+    }
+
+    // The next frame of a value that must have one
+    fn expect_frame(&mut self) -> Result<&'de [u8], Error> {
+        self.next_frame()?.ok_or(Error::Invalid { expected: "more elements" })
+    }
+
+    // Check that every frame was consumed
+    fn finish(&mut self) -> Result<(), Error> {
+        match self.next_frame()? {
+            Some(_) => Err(Error::TrailingFrames),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'de> de::SeqAccess<'de> for Frames<'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, Error> {
+        match self.next_frame()? {
+            Some(payload) => seed.deserialize(Deserializer::from_bytes(payload)).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<'de> de::MapAccess<'de> for Frames<'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Error> {
+        de::SeqAccess::next_element_seed(self, seed)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        seed.deserialize(Deserializer::from_bytes(self.expect_frame()?))
+    }
+}
+
+// ===== impl Enum =====
+
+impl<'de> de::EnumAccess<'de> for Enum<'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(mut self, seed: V) -> Result<(V::Value, Self), Error> {
+        let name = self.frames.expect_frame()?;
+        let variant = seed.deserialize(Deserializer::from_bytes(name))?;
+
+        Ok((variant, self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for Enum<'de> {
+    type Error = Error;
+
+    fn unit_variant(mut self) -> Result<(), Error> {
+        self.frames.finish()
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(mut self, seed: T) -> Result<T::Value, Error> {
+        let value = seed.deserialize(Deserializer::from_bytes(self.frames.expect_frame()?))?;
+
+        self.frames.finish()?;
+        Ok(value)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(mut self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        let fields = Deserializer::from_bytes(self.frames.expect_frame()?);
+        let value = de::Deserializer::deserialize_seq(fields, visitor)?;
+
+        self.frames.finish()?;
+        Ok(value)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V)
+        -> Result<V::Value, Error>
+    {
+        self.tuple_variant(0, visitor)
+    }
+}
+
+// ===== impl Error =====
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Message(ref msg) => f.write_str(msg),
+            Error::Netstring(ref e) => e.fmt(f),
+            Error::Invalid { expected } => write!(f, "invalid value, expected {}", expected),
+            Error::TrailingFrames => f.write_str("list holds more elements than expected"),
+        }
+    }
+}
+
+impl ser::StdError for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Error {
+        Error::Message(msg.to_string())
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: Display>(msg: T) -> Error {
+        Error::Message(msg.to_string())
+    }
+}
+
+impl From<NetstringError> for Error {
+    fn from(e: NetstringError) -> Error {
+        Error::Netstring(e)
+    }
+}
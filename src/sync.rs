@@ -0,0 +1,241 @@
+//! Blocking netstring framing over `std::io`.
+//!
+//! [`Reader`] and [`Writer`] frame any `io::Read` and `io::Write`, without
+//! requiring a runtime. They are configured through the same [`Builder`] as
+//! the async types, see [`Builder::new_sync_read`] and
+//! [`Builder::new_sync_write`]. The memory budget and the write queue
+//! settings do not apply.
+//!
+//! # Examples
+//!
+//! ```
+//! use tokio_netstring::sync::{Reader, Writer};
+//!
+//! # fn main() -> std::io::Result<()> {
+//! let mut writer = Writer::new(Vec::new());
+//! writer.write_frame(b"hello")?;
+//! writer.write_frame(b"world")?;
+//!
+//! let frames = Reader::new(&writer.get_ref()[..]).collect::<Result<Vec<_>, _>>()?;
+//! assert_eq!(frames, vec![b"hello".to_vec(), b"world".to_vec()]);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Builder`]: ../struct.Builder.html
+//! [`Builder::new_sync_read`]: ../struct.Builder.html#method.new_sync_read
+//! [`Builder::new_sync_write`]: ../struct.Builder.html#method.new_sync_write
+
+use crate::{Builder, NetstringDecoder, NetstringEncoder, NetstringError, READ_CHUNK_LEN};
+
+use bytes::{Buf, BytesMut};
+
+use std::io::{self, IoSlice, Read, Write};
+
+/// Reads netstring frames from an `io::Read`.
+///
+/// `Reader` is an `Iterator` over the frame payloads. Iteration ends at the
+/// end of the stream, or after the first error unless it is a
+/// `NetstringError::Corrupt` reported in resync mode.
+#[derive(Debug)]
+pub struct Reader<R> {
+    inner: R,
+
+    // Frame decoder
+    decoder: NetstringDecoder,
+
+    // Bytes read from `inner` and not decoded yet
+    buffer: BytesMut,
+
+    // Reads land here before being appended to `buffer`, so the buffer
+    // capacity reserved for a frame is never zeroed
+    chunk: Vec<u8>,
+
+    // `inner` reached EOF
+    eof: bool,
+
+    // No more frames will be read, after EOF or an error
+    done: bool,
+}
+
+/// Writes netstring frames to an `io::Write`.
+///
+/// Each frame is handed to the writer as soon as it is submitted, there is
+/// no buffering on top of the one `W` does.
+#[derive(Debug)]
+pub struct Writer<W> {
+    inner: W,
+
+    // Frame head encoder
+    encoder: NetstringEncoder,
+}
+
+// ===== impl Reader =====
+
+impl<R: Read> Reader<R> {
+    /// Creates a new `Reader` with default configuration values.
+    pub fn new(inner: R) -> Reader<R> {
+        Builder::new().new_sync_read(inner)
+    }
+
+    pub(crate) fn with_decoder(inner: R, decoder: NetstringDecoder) -> Reader<R> {
+        Reader {
+            inner,
+            decoder,
+            buffer: BytesMut::new(),
+            chunk: vec![0; READ_CHUNK_LEN],
+            eof: false,
+            done: false,
+        }
+    }
+
+    /// Reads the next frame into `buf`, replacing its content.
+    ///
+    /// Returns `false`, leaving `buf` untouched, if the stream ended between
+    /// two frames.
+    pub fn read_frame_into(&mut self, buf: &mut Vec<u8>) -> io::Result<bool> {
+        match self.next_frame()? {
+            Some(frame) => {
+                buf.clear();
+                buf.extend_from_slice(&frame);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn next_frame(&mut self) -> io::Result<Option<BytesMut>> {
+        loop {
+            if self.done {
+                return Ok(None);
+            }
+
+            let res = if self.eof {
+                self.decoder.decode_buf_eof(&mut self.buffer)
+            } else {
+                self.decoder.decode_buf(&mut self.buffer)
+            };
+
+            match res {
+                Ok(Some(frame)) => return Ok(Some(frame)),
+                Ok(None) if self.eof => {
+                    self.done = true;
+                    return Ok(None);
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    // The decoder recovers from corrupt frames on its own
+                    if !matches!(e, NetstringError::Corrupt { .. }) {
+                        self.done = true;
+                    }
+
+                    return Err(e.into());
+                }
+            }
+
+            let n = match self.inner.read(&mut self.chunk) {
+                Ok(n) => n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    self.done = true;
+                    return Err(e);
+                }
+            };
+
+            self.buffer.extend_from_slice(&self.chunk[..n]);
+            self.eof = n == 0;
+        }
+    }
+}
+
+impl<R> Reader<R> {
+    /// Returns a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying reader.
+    ///
+    /// Note that care should be taken to not tamper with the underlying
+    /// stream of data, as it may corrupt the stream of frames otherwise
+    /// being worked with.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Consumes the `Reader`, returning its underlying reader.
+    ///
+    /// Note that the bytes read ahead of the last frame are lost.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Iterator for Reader<R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<io::Result<Vec<u8>>> {
+        self.next_frame().map(|frame| frame.map(|frame| frame.to_vec())).transpose()
+    }
+}
+
+// ===== impl Writer =====
+
+impl<W: Write> Writer<W> {
+    /// Creates a new `Writer` with default configuration values.
+    pub fn new(inner: W) -> Writer<W> {
+        Builder::new().new_sync_write(inner)
+    }
+
+    pub(crate) fn with_encoder(inner: W, encoder: NetstringEncoder) -> Writer<W> {
+        Writer { inner, encoder }
+    }
+
+    /// Writes `payload` as a single frame.
+    ///
+    /// Payloads over the max frame length are rejected with
+    /// `NetstringError::FrameTooLarge`, before anything is written.
+    pub fn write_frame(&mut self, payload: &[u8]) -> io::Result<()> {
+        let mut frame = self.encoder.encode_frame(payload)?;
+
+        while frame.has_remaining() {
+            let mut slices = [IoSlice::new(&[]); 4];
+            let cnt = frame.chunks_vectored(&mut slices);
+
+            match self.inner.write_vectored(&slices[..cnt]) {
+                Ok(0) => return Err(io::Error::new(io::ErrorKind::WriteZero, "failed to write frame")),
+                Ok(n) => frame.advance(n),
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<W> Writer<W> {
+    /// Returns a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying writer.
+    ///
+    /// Note that care should be taken to not tamper with the underlying
+    /// stream of data, as it may corrupt the stream of frames otherwise
+    /// being worked with.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Consumes the `Writer`, returning its underlying writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
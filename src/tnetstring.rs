@@ -0,0 +1,1411 @@
+//! Tagged netstrings, as used by Mongrel2.
+//!
+//! A tnetstring is a netstring whose `','` terminator is replaced by a tag
+//! giving the type of its payload:
+//!
+//! | Tag | Type       | Payload                                      |
+//! |-----|------------|----------------------------------------------|
+//! | `,` | string     | the bytes as is                              |
+//! | `#` | integer    | decimal digits, optionally signed            |
+//! | `^` | float      | decimal representation                       |
+//! | `!` | boolean    | `true` or `false`                            |
+//! | `~` | null       | nothing                                      |
+//! | `]` | list       | the tnetstrings of the elements              |
+//! | `}` | dictionary | the tnetstrings of the keys and values, alternating |
+//!
+//! Tnetstrings are parsed like netstrings, with the type tags accepted in
+//! place of the `','`. The decoders honor the same configuration settings,
+//! report an unknown tag as `NetstringError::MissingTerminator` and a
+//! payload not valid for its tag as `NetstringError::InvalidValue`.
+//!
+//! Up to 128 lists and dictionaries can be nested in one another. Deeper
+//! values are neither decoded nor encoded, and reported as
+//! `NetstringError::NestingTooDeep`.
+//!
+//! Floats are encoded in plain decimal, without an exponent. NaN and the
+//! infinities have no such representation, and are rejected as
+//! `NetstringError::InvalidValue`.
+//!
+//! A decoded tnetstring is a [`Value`]. Values are encoded with [`encode`]
+//! and decoded with [`decode_one`], or over a stream with the
+//! [`TnetstringDecoder`] and [`TnetstringEncoder`] codecs. With the `serde`
+//! feature, `Value` implements `Serialize` and `Deserialize`, and
+//! [`to_bytes`] and [`from_bytes`] encode any serializable value.
+//!
+//! # Examples
+//!
+//! ```
+//! use tokio_netstring::tnetstring::{self, Value};
+//!
+//! let value = Value::List(vec![Value::from("hello"), Value::Integer(42), Value::Null]);
+//! let encoded = tnetstring::encode(&value).unwrap();
+//! assert_eq!(encoded, b"16:5:hello,2:42#0:~]");
+//!
+//! let (decoded, rest) = tnetstring::decode_one(&encoded).unwrap();
+//! assert_eq!(decoded, value);
+//! assert!(rest.is_empty());
+//! ```
+//!
+//! [`Value`]: enum.Value.html
+//! [`encode`]: fn.encode.html
+//! [`decode_one`]: fn.decode_one.html
+//! [`TnetstringDecoder`]: struct.TnetstringDecoder.html
+//! [`TnetstringEncoder`]: struct.TnetstringEncoder.html
+//! [`to_bytes`]: fn.to_bytes.html
+//! [`from_bytes`]: fn.from_bytes.html
+
+use crate::nested::nested_builder;
+use crate::parser::{Head, ParseState};
+use crate::{encoded_len, Builder, NetstringDecoder, NetstringError, Status};
+
+use alloc::collections::{btree_map, BTreeMap};
+use alloc::vec::Vec;
+
+use bytes::{BufMut, Bytes, BytesMut};
+
+#[cfg(feature = "tokio")]
+use tokio_util::codec;
+
+#[cfg(feature = "serde")]
+use crate::serde::Error;
+#[cfg(feature = "serde")]
+use ::serde::de::value::{MapDeserializer, SeqDeserializer};
+#[cfg(feature = "serde")]
+use ::serde::de::{self, DeserializeOwned, IntoDeserializer, Visitor};
+#[cfg(feature = "serde")]
+use ::serde::ser::{self, Serialize};
+
+use core::fmt::{self, Write};
+use core::ops::Range;
+use core::slice;
+use core::str::{self, FromStr};
+
+// The type tags ending tnetstrings, in place of the ','
+const TNETSTRING_TAGS: &[u8] = b",#^!~}]";
+
+// Lists and dictionaries nested deeper than this are rejected, bounding the
+// recursion of the decoder. The encoder applies the same limit, so that
+// whatever it writes can be read back.
+const MAX_NESTING: usize = 128;
+
+/// A decoded tnetstring.
+///
+/// Strings are byte strings, sharing the memory of the buffer they were
+/// decoded from. Dictionary keys are strings as well.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// A string, tagged `,`.
+    String(Bytes),
+
+    /// An integer, tagged `#`.
+    Integer(i64),
+
+    /// A float, tagged `^`.
+    Float(f64),
+
+    /// A boolean, tagged `!`.
+    Bool(bool),
+
+    /// The null value, tagged `~`.
+    Null,
+
+    /// A dictionary, tagged `}`.
+    Dict(BTreeMap<Bytes, Value>),
+
+    /// A list, tagged `]`.
+    List(Vec<Value>),
+}
+
+/// Decodes tnetstrings out of a `BytesMut`.
+///
+/// `TnetstringDecoder` implements the `tokio-util` [`Decoder`] trait,
+/// yielding [`Value`]s. It is configured through
+/// [`Builder::new_tnetstring_decoder`]. The max frame length applies to the
+/// outermost tnetstring, the values nested in it are bounded by its payload.
+/// The `strip_frame` setting does not apply.
+///
+/// A payload not valid for its type tag ends the stream, even in resync
+/// mode.
+///
+/// [`Decoder`]: https://docs.rs/tokio-util/0.7/tokio_util/codec/trait.Decoder.html
+/// [`Value`]: enum.Value.html
+/// [`Builder::new_tnetstring_decoder`]: ../struct.Builder.html#method.new_tnetstring_decoder
+#[derive(Debug)]
+pub struct TnetstringDecoder {
+    // Frame decoder, accepting the type tags as terminators
+    inner: NetstringDecoder,
+
+    // Configuration values for the nested values
+    nested: Builder,
+}
+
+/// Encodes [`Value`]s as tnetstrings into a `BytesMut`.
+///
+/// `TnetstringEncoder` implements the `tokio-util` [`Encoder`] trait for
+/// `Value` and `&Value`. It is configured through
+/// [`Builder::new_tnetstring_encoder`], the max frame length and write
+/// prefix apply to the outermost tnetstring.
+///
+/// [`Encoder`]: https://docs.rs/tokio-util/0.7/tokio_util/codec/trait.Encoder.html
+/// [`Value`]: enum.Value.html
+/// [`Builder::new_tnetstring_encoder`]: ../struct.Builder.html#method.new_tnetstring_encoder
+#[derive(Debug, Clone)]
+pub struct TnetstringEncoder {
+    // Configuration values
+    builder: Builder,
+}
+
+// The values held by a list or dictionary, in the order they are encoded.
+// Dictionary values come with their key.
+enum Children<'a> {
+    List(slice::Iter<'a, Value>),
+    Dict(btree_map::Iter<'a, Bytes, Value>),
+}
+
+// Writes formatted text into a `BufMut`
+struct Text<'a, M>(&'a mut M);
+
+// Counts the bytes of formatted text
+struct Count(usize);
+
+// Formats a float in plain decimal, without an exponent
+struct Decimal(f64);
+
+/// Encodes `value` as a tnetstring.
+///
+/// Unlike [`TnetstringEncoder`], no max frame length applies. Values
+/// nesting more than 128 lists and dictionaries are rejected with
+/// `NetstringError::NestingTooDeep`, and non-finite floats with
+/// `NetstringError::InvalidValue`.
+///
+/// # Examples
+///
+/// ```
+/// use tokio_netstring::tnetstring::{self, Value};
+///
+/// assert_eq!(tnetstring::encode(&Value::Bool(true)).unwrap(), b"4:true!");
+/// ```
+///
+/// [`TnetstringEncoder`]: struct.TnetstringEncoder.html
+pub fn encode(value: &Value) -> Result<Vec<u8>, NetstringError> {
+    let lens = value.payload_lens(true)?;
+    let mut dst = Vec::with_capacity(frame_len(lens[0]));
+
+    value.encode_with(&lens, &mut dst);
+    Ok(dst)
+}
+
+/// Decodes the tnetstring at the start of `buf`.
+///
+/// Returns its value along with the rest of `buf`. The strings of the value
+/// are copied out of `buf`. Frames are validated like
+/// [`TnetstringDecoder`] does with default configuration values, `buf` not
+/// starting with a complete tnetstring is reported as
+/// `NetstringError::UnexpectedEof`.
+///
+/// # Examples
+///
+/// ```
+/// use tokio_netstring::tnetstring::{self, Value};
+///
+/// let (value, rest) = tnetstring::decode_one(b"2:42#0:~").unwrap();
+///
+/// assert_eq!(value, Value::Integer(42));
+/// assert_eq!(rest, b"0:~");
+/// ```
+///
+/// [`TnetstringDecoder`]: struct.TnetstringDecoder.html
+pub fn decode_one(buf: &[u8]) -> Result<(Value, &[u8]), NetstringError> {
+    let builder = Builder::new();
+    let payload = parse_frame(&tagged(&builder), buf)?;
+
+    let tag = buf[payload.end];
+    let rest = &buf[payload.end + 1..];
+    let payload = Bytes::copy_from_slice(&buf[payload]);
+
+    Ok((Value::from_payload(&nested(&builder), tag, payload, 0)?, rest))
+}
+
+/// Serializes `value` as a tnetstring.
+///
+/// Structs and maps are encoded as dictionaries, sequences and tuples as
+/// lists, and `None` and `()` as null. Enum variants are encoded as their
+/// name, or as a dictionary holding their value under their name. Map keys
+/// must serialize to strings.
+///
+/// # Examples
+///
+/// ```
+/// use tokio_netstring::tnetstring::to_bytes;
+///
+/// # fn main() -> Result<(), tokio_netstring::serde::Error> {
+/// assert_eq!(to_bytes(&(1, "a"))?, b"8:1:1#1:a,]");
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "serde")]
+pub fn to_bytes<T: ?Sized + Serialize>(value: &T) -> Result<Vec<u8>, Error> {
+    Ok(encode(&to_value(value)?)?)
+}
+
+/// Deserializes a value from the tnetstring held by `buf`.
+///
+/// `buf` must hold exactly one tnetstring.
+///
+/// # Examples
+///
+/// ```
+/// use tokio_netstring::tnetstring::from_bytes;
+///
+/// # fn main() -> Result<(), tokio_netstring::serde::Error> {
+/// let (n, name): (u8, String) = from_bytes(b"8:1:1#1:a,]")?;
+/// assert_eq!((n, name.as_str()), (1, "a"));
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "serde")]
+pub fn from_bytes<T: DeserializeOwned>(buf: &[u8]) -> Result<T, Error> {
+    let (value, rest) = decode_one(buf)?;
+
+    if !rest.is_empty() {
+        return Err(Error::TrailingFrames);
+    }
+
+    from_value(value)
+}
+
+/// Converts any serializable value into a `Value`.
+///
+/// See [`to_bytes`] for how values are represented.
+///
+/// [`to_bytes`]: fn.to_bytes.html
+#[cfg(feature = "serde")]
+pub fn to_value<T: ?Sized + Serialize>(value: &T) -> Result<Value, Error> {
+    value.serialize(ValueSerializer)
+}
+
+/// Converts a `Value` into any deserializable value.
+#[cfg(feature = "serde")]
+pub fn from_value<T: DeserializeOwned>(value: Value) -> Result<T, Error> {
+    T::deserialize(value)
+}
+
+// `builder`, accepting the type tags in place of the ','
+fn tagged(builder: &Builder) -> Builder {
+    let mut tagged = builder.clone();
+    tagged.terminators = TNETSTRING_TAGS;
+    tagged
+}
+
+// `builder`, for the values nested in lists and dictionaries, which are
+// bounded by the payload holding them
+fn nested(builder: &Builder) -> Builder {
+    nested_builder(&tagged(builder))
+}
+
+// Find the tnetstring at the start of `buf`, returning the range of its
+// payload. The type tag follows the payload.
+fn parse_frame(builder: &Builder, buf: &[u8]) -> Result<Range<usize>, NetstringError> {
+    let mut state = ParseState::default();
+
+    match state.parse(builder, buf) {
+        Status::Frame(payload) => Ok(payload),
+        Status::NeedMore(_) => Err(NetstringError::UnexpectedEof {
+            expected: state.frame_len().map(|frame_len| frame_len - buf.len()),
+            buffered: buf.len(),
+        }),
+        Status::Oversize { len, .. } => Err(NetstringError::FrameTooLarge {
+            declared: len,
+            max: builder.max_frame_len,
+        }),
+        Status::Error(e) => Err(e),
+    }
+}
+
+// Number of bytes of a tnetstring carrying `payload_len` bytes
+fn frame_len(payload_len: usize) -> usize {
+    Head::len_of(payload_len) + payload_len + 1
+}
+
+// Parse a number out of its decimal representation
+fn parse_text<T: FromStr>(payload: &[u8]) -> Option<T> {
+    str::from_utf8(payload).ok()?.parse().ok()
+}
+
+// ===== impl Value =====
+
+impl Value {
+    /// Returns the type tag of the value.
+    pub fn tag(&self) -> u8 {
+        match *self {
+            Value::String(..) => b',',
+            Value::Integer(..) => b'#',
+            Value::Float(..) => b'^',
+            Value::Bool(..) => b'!',
+            Value::Null => b'~',
+            Value::Dict(..) => b'}',
+            Value::List(..) => b']',
+        }
+    }
+
+    /// Returns the number of bytes the value takes once encoded.
+    ///
+    /// The length is returned even for values that cannot be encoded,
+    /// nested too deep or holding non-finite floats.
+    pub fn encoded_len(&self) -> usize {
+        match self.payload_lens(false) {
+            Ok(lens) => frame_len(lens[0]),
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Encodes the value as a tnetstring into any `BufMut`.
+    ///
+    /// Values nesting more than 128 lists and dictionaries are rejected
+    /// with `NetstringError::NestingTooDeep`, and non-finite floats with
+    /// `NetstringError::InvalidValue`, before anything is written.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `dst` does not have [`encoded_len`] bytes of remaining
+    /// capacity, like `BufMut::put`.
+    ///
+    /// [`encoded_len`]: #method.encoded_len
+    pub fn encode_into<M: BufMut>(&self, dst: &mut M) -> Result<(), NetstringError> {
+        self.encode_with(&self.payload_lens(true)?, dst);
+        Ok(())
+    }
+
+    // Payload lengths of the value and of every value nested in it, in the
+    // order they are encoded. Nested values are gone through once, without
+    // recursing. When `strict`, values that cannot be encoded are rejected:
+    // those nesting more than `MAX_NESTING` lists and dictionaries, and
+    // non-finite floats.
+    fn payload_lens(&self, strict: bool) -> Result<Vec<usize>, NetstringError> {
+        let mut lens = Vec::new();
+
+        // Lists and dictionaries being measured, along with the index of
+        // their length
+        let mut stack: Vec<(usize, Children<'_>)> = Vec::new();
+        let mut value = self;
+
+        loop {
+            match value.children() {
+                Some(_) if strict && stack.len() == MAX_NESTING => {
+                    return Err(NetstringError::NestingTooDeep { max: MAX_NESTING });
+                }
+                None if strict && matches!(*value, Value::Float(n) if !n.is_finite()) => {
+                    return Err(NetstringError::InvalidValue { tag: b'^' });
+                }
+                Some(children) => {
+                    stack.push((lens.len(), children));
+                    lens.push(0);
+                }
+                None => {
+                    let len = value.scalar_len();
+                    lens.push(len);
+
+                    if let Some(&(parent, _)) = stack.last() {
+                        lens[parent] += frame_len(len);
+                    }
+                }
+            }
+
+            // Move on to the next value, completing the lists and
+            // dictionaries it ends
+            value = loop {
+                let (index, children) = match stack.last_mut() {
+                    Some(&mut (index, ref mut children)) => (index, children),
+                    None => return Ok(lens),
+                };
+
+                match children.next() {
+                    Some((key, next)) => {
+                        // Note: a key is encoded like a netstring
+                        if let Some(key) = key {
+                            lens[index] += encoded_len(key.len());
+                        }
+
+                        break next;
+                    }
+                    None => {
+                        stack.pop();
+
+                        if let Some(&(parent, _)) = stack.last() {
+                            lens[parent] += frame_len(lens[index]);
+                        }
+                    }
+                }
+            };
+        }
+    }
+
+    // Encode the value, given its `payload_lens`
+    fn encode_with<M: BufMut>(&self, lens: &[usize], dst: &mut M) {
+        let mut lens = lens.iter();
+
+        // Lists and dictionaries being encoded, along with their tag
+        let mut stack: Vec<(u8, Children<'_>)> = Vec::new();
+        let mut value = self;
+
+        loop {
+            dst.put(Head::new(*lens.next().expect("missing payload length")));
+
+            match value.children() {
+                Some(children) => stack.push((value.tag(), children)),
+                None => {
+                    value.put_scalar(dst);
+                    dst.put_u8(value.tag());
+                }
+            }
+
+            value = loop {
+                let (tag, children) = match stack.last_mut() {
+                    Some(&mut (tag, ref mut children)) => (tag, children),
+                    None => return,
+                };
+
+                match children.next() {
+                    Some((key, next)) => {
+                        if let Some(key) = key {
+                            crate::encode_into(key, dst);
+                        }
+
+                        break next;
+                    }
+                    None => {
+                        dst.put_u8(tag);
+                        stack.pop();
+                    }
+                }
+            };
+        }
+    }
+
+    // The values held by a list or dictionary, `None` for other values
+    fn children(&self) -> Option<Children<'_>> {
+        match *self {
+            Value::Dict(ref dict) => Some(Children::Dict(dict.iter())),
+            Value::List(ref items) => Some(Children::List(items.iter())),
+            _ => None,
+        }
+    }
+
+    // Number of bytes of the encoded payload of a value other than a list
+    // or dictionary
+    fn scalar_len(&self) -> usize {
+        match *self {
+            Value::String(ref s) => s.len(),
+            Value::Integer(n) => Count::of(format_args!("{}", n)),
+            Value::Float(n) => Count::of(format_args!("{}", Decimal(n))),
+            Value::Bool(b) => if b { 4 } else { 5 },
+            Value::Null | Value::Dict(..) | Value::List(..) => 0,
+        }
+    }
+
+    // Write the payload of a value other than a list or dictionary
+    fn put_scalar<M: BufMut>(&self, dst: &mut M) {
+        match *self {
+            Value::String(ref s) => dst.put_slice(s),
+            Value::Integer(n) => Text(dst).put(format_args!("{}", n)),
+            Value::Float(n) => Text(dst).put(format_args!("{}", Decimal(n))),
+            Value::Bool(b) => dst.put_slice(if b { b"true" } else { b"false" }),
+            Value::Null | Value::Dict(..) | Value::List(..) => {}
+        }
+    }
+
+    // Decode a value out of its type tag and payload, `depth` being the
+    // number of lists and dictionaries holding it
+    fn from_payload(nested: &Builder, tag: u8, payload: Bytes, depth: usize) -> Result<Value, NetstringError> {
+        let invalid = NetstringError::InvalidValue { tag };
+
+        match tag {
+            b',' => Ok(Value::String(payload)),
+            b'#' => parse_text(&payload).map(Value::Integer).ok_or(invalid),
+            b'^' => parse_text(&payload).map(Value::Float).ok_or(invalid),
+            b'!' => match &payload[..] {
+                b"true" => Ok(Value::Bool(true)),
+                b"false" => Ok(Value::Bool(false)),
+                _ => Err(invalid),
+            },
+            b'~' if payload.is_empty() => Ok(Value::Null),
+            b']' | b'}' if depth < MAX_NESTING => {
+                let mut items = Vec::new();
+                let mut pos = 0;
+
+                while pos < payload.len() {
+                    let range = parse_frame(nested, &payload[pos..])?;
+                    let tag = payload[pos + range.end];
+                    let item = payload.slice(pos + range.start..pos + range.end);
+
+                    items.push(Value::from_payload(nested, tag, item, depth + 1)?);
+                    pos += range.end + 1;
+                }
+
+                if tag == b']' {
+                    return Ok(Value::List(items));
+                }
+
+                // Keys and values alternate, keys are strings
+                let mut dict = BTreeMap::new();
+                let mut items = items.into_iter();
+
+                while let Some(key) = items.next() {
+                    match (key, items.next()) {
+                        (Value::String(key), Some(value)) => dict.insert(key, value),
+                        _ => return Err(invalid),
+                    };
+                }
+
+                Ok(Value::Dict(dict))
+            }
+            b']' | b'}' => Err(NetstringError::NestingTooDeep { max: MAX_NESTING }),
+            _ => Err(invalid),
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Value {
+        Value::String(Bytes::copy_from_slice(s.as_bytes()))
+    }
+}
+
+impl From<Bytes> for Value {
+    fn from(s: Bytes) -> Value {
+        Value::String(s)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(n: i64) -> Value {
+        Value::Integer(n)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Value {
+        Value::Float(n)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Value {
+        Value::Bool(b)
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(items: Vec<Value>) -> Value {
+        Value::List(items)
+    }
+}
+
+// ===== impl TnetstringDecoder =====
+
+impl TnetstringDecoder {
+    /// Creates a new `TnetstringDecoder` with default configuration values.
+    pub fn new() -> TnetstringDecoder {
+        Builder::new().new_tnetstring_decoder()
+    }
+
+    pub(crate) fn from_builder(builder: &Builder) -> TnetstringDecoder {
+        TnetstringDecoder {
+            inner: tagged(builder).new_decoder(),
+            nested: nested(builder),
+        }
+    }
+
+    /// Decodes the next value out of `src`.
+    ///
+    /// This is the runtime independent counterpart of `Decoder::decode`.
+    /// The tnetstring is split off `src`, `None` is returned if `src` does
+    /// not hold a complete one yet.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokio_netstring::tnetstring::{TnetstringDecoder, Value};
+    /// use bytes::BytesMut;
+    ///
+    /// let mut decoder = TnetstringDecoder::new();
+    /// let mut src = BytesMut::from(&b"4:true!5:hel"[..]);
+    ///
+    /// assert_eq!(decoder.decode_buf(&mut src).unwrap(), Some(Value::Bool(true)));
+    /// assert_eq!(decoder.decode_buf(&mut src).unwrap(), None);
+    /// ```
+    pub fn decode_buf(&mut self, src: &mut BytesMut) -> Result<Option<Value>, NetstringError> {
+        let frame = match self.inner.decode_frame(src)? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+
+        let end = frame.head + frame.len;
+        let tag = frame.buf[end];
+        let payload = frame.buf.freeze().slice(frame.head..end);
+
+        Value::from_payload(&self.nested, tag, payload, 0).map(Some)
+    }
+
+    /// Decodes the next value out of `src`, once no more bytes will come.
+    ///
+    /// This is the runtime independent counterpart of `Decoder::decode_eof`.
+    /// Bytes left over in `src` that do not form a complete tnetstring are
+    /// reported as `NetstringError::UnexpectedEof`.
+    pub fn decode_buf_eof(&mut self, src: &mut BytesMut) -> Result<Option<Value>, NetstringError> {
+        match self.decode_buf(src)? {
+            Some(value) => Ok(Some(value)),
+            None if !self.inner.in_frame(src) => Ok(None),
+            None => Err(self.inner.eof_error(src)),
+        }
+    }
+}
+
+impl Default for TnetstringDecoder {
+    fn default() -> TnetstringDecoder {
+        TnetstringDecoder::new()
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl codec::Decoder for TnetstringDecoder {
+    type Item = Value;
+    type Error = NetstringError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Value>, NetstringError> {
+        self.decode_buf(src)
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Value>, NetstringError> {
+        self.decode_buf_eof(src)
+    }
+}
+
+// ===== impl TnetstringEncoder =====
+
+impl TnetstringEncoder {
+    /// Creates a new `TnetstringEncoder` with default configuration values.
+    pub fn new() -> TnetstringEncoder {
+        Builder::new().new_tnetstring_encoder()
+    }
+
+    pub(crate) fn from_builder(builder: &Builder) -> TnetstringEncoder {
+        TnetstringEncoder { builder: builder.clone() }
+    }
+
+    /// Encodes `value` as a tnetstring into any `BufMut`.
+    ///
+    /// This is the runtime independent counterpart of the `Encoder`
+    /// implementation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `dst` does not have enough remaining capacity for the
+    /// tnetstring, like `BufMut::put`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokio_netstring::tnetstring::{TnetstringEncoder, Value};
+    ///
+    /// let mut dst = Vec::new();
+    /// TnetstringEncoder::new().encode_buf(&Value::Integer(-7), &mut dst).unwrap();
+    /// assert_eq!(dst, b"2:-7#");
+    /// ```
+    pub fn encode_buf<M: BufMut>(&self, value: &Value, dst: &mut M) -> Result<(), NetstringError> {
+        let lens = self.measure(value)?;
+
+        dst.put_slice(self.prefix());
+        value.encode_with(&lens, dst);
+
+        Ok(())
+    }
+
+    // Check that `value` can be encoded, returning its `payload_lens`
+    fn measure(&self, value: &Value) -> Result<Vec<usize>, NetstringError> {
+        let lens = value.payload_lens(true)?;
+
+        if let Some(ref prefix) = self.builder.write_prefix {
+            self.builder.check_prefix(prefix)?;
+        }
+
+        if lens[0] > self.builder.max_frame_len {
+            return Err(NetstringError::FrameTooLarge {
+                declared: lens[0] as u64,
+                max: self.builder.max_frame_len,
+            });
+        }
+
+        Ok(lens)
+    }
+
+    // The bytes written before the length field
+    fn prefix(&self) -> &[u8] {
+        self.builder.write_prefix.as_deref().unwrap_or(&[])
+    }
+}
+
+impl Default for TnetstringEncoder {
+    fn default() -> TnetstringEncoder {
+        TnetstringEncoder::new()
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl codec::Encoder<&Value> for TnetstringEncoder {
+    type Error = NetstringError;
+
+    fn encode(&mut self, item: &Value, dst: &mut BytesMut) -> Result<(), NetstringError> {
+        let lens = self.measure(item)?;
+
+        dst.reserve(self.prefix().len() + frame_len(lens[0]));
+        dst.put_slice(self.prefix());
+        item.encode_with(&lens, dst);
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl codec::Encoder<Value> for TnetstringEncoder {
+    type Error = NetstringError;
+
+    fn encode(&mut self, item: Value, dst: &mut BytesMut) -> Result<(), NetstringError> {
+        codec::Encoder::encode(self, &item, dst)
+    }
+}
+
+// ===== impl Children =====
+
+impl<'a> Iterator for Children<'a> {
+    type Item = (Option<&'a Bytes>, &'a Value);
+
+    fn next(&mut self) -> Option<(Option<&'a Bytes>, &'a Value)> {
+        match *self {
+            Children::List(ref mut items) => items.next().map(|item| (None, item)),
+            Children::Dict(ref mut dict) => dict.next().map(|(key, value)| (Some(key), value)),
+        }
+    }
+}
+
+// ===== impl Text =====
+
+impl<M: BufMut> Text<'_, M> {
+    fn put(mut self, args: fmt::Arguments) {
+        // Note: writing to a `BufMut` cannot fail
+        let _ = self.write_fmt(args);
+    }
+}
+
+impl<M: BufMut> fmt::Write for Text<'_, M> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.put_slice(s.as_bytes());
+        Ok(())
+    }
+}
+
+// ===== impl Count =====
+
+impl Count {
+    fn of(args: fmt::Arguments) -> usize {
+        let mut count = Count(0);
+
+        // Note: counting cannot fail
+        let _ = count.write_fmt(args);
+        count.0
+    }
+}
+
+impl fmt::Write for Count {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.len();
+        Ok(())
+    }
+}
+
+// ===== impl Decimal =====
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Note: `Display` never uses an exponent, but drops the fraction of
+        // whole numbers
+        if self.0 % 1.0 == 0.0 {
+            write!(f, "{}.0", self.0)
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}
+
+// ===== impl Serialize for Value =====
+
+#[cfg(feature = "serde")]
+impl Serialize for Value {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            Value::String(ref s) => StringRef(s).serialize(serializer),
+            Value::Integer(n) => serializer.serialize_i64(n),
+            Value::Float(n) => serializer.serialize_f64(n),
+            Value::Bool(b) => serializer.serialize_bool(b),
+            Value::Null => serializer.serialize_unit(),
+            Value::Dict(ref dict) => serializer.collect_map(dict.iter().map(|(key, value)| (StringRef(key), value))),
+            Value::List(ref items) => serializer.collect_seq(items),
+        }
+    }
+}
+
+// A tnetstring string, serialized as a `str` if it is valid UTF-8
+#[cfg(feature = "serde")]
+struct StringRef<'a>(&'a [u8]);
+
+#[cfg(feature = "serde")]
+impl Serialize for StringRef<'_> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match str::from_utf8(self.0) {
+            Ok(s) => serializer.serialize_str(s),
+            Err(_) => serializer.serialize_bytes(self.0),
+        }
+    }
+}
+
+// ===== impl Deserialize for Value =====
+
+#[cfg(feature = "serde")]
+impl<'de> de::Deserialize<'de> for Value {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+#[cfg(feature = "serde")]
+struct ValueVisitor;
+
+#[cfg(feature = "serde")]
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a tnetstring value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::Integer(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Value, E> {
+        i64::try_from(v).map(Value::Integer).map_err(|_| E::invalid_value(de::Unexpected::Unsigned(v), &self))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Value, E> {
+        Ok(Value::Float(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_string<E: de::Error>(self, v: alloc::string::String) -> Result<Value, E> {
+        Ok(Value::String(v.into()))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Value, E> {
+        Ok(Value::String(Bytes::copy_from_slice(v)))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Value, E> {
+        Ok(Value::String(v.into()))
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        de::Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut items = Vec::new();
+
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+
+        Ok(Value::List(items))
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut dict = BTreeMap::new();
+
+        while let Some((key, value)) = map.next_entry()? {
+            match key {
+                Value::String(key) => dict.insert(key, value),
+                _ => return Err(de::Error::custom("dictionary keys must be strings")),
+            };
+        }
+
+        Ok(Value::Dict(dict))
+    }
+}
+
+// ===== impl ValueSerializer =====
+
+// Serializes any value into a `Value`
+#[cfg(feature = "serde")]
+struct ValueSerializer;
+
+// Serializes sequences and tuples into a list, held under the variant name
+// for tuple variants
+#[cfg(feature = "serde")]
+struct ListSerializer {
+    items: Vec<Value>,
+    variant: Option<&'static str>,
+}
+
+// Serializes maps and structs into a dictionary, held under the variant
+// name for struct variants
+#[cfg(feature = "serde")]
+struct DictSerializer {
+    dict: BTreeMap<Bytes, Value>,
+    key: Option<Bytes>,
+    variant: Option<&'static str>,
+}
+
+// A dictionary holding `value` under the variant name
+#[cfg(feature = "serde")]
+fn variant(name: &'static str, value: Value) -> Value {
+    let mut dict = BTreeMap::new();
+    dict.insert(Bytes::from_static(name.as_bytes()), value);
+    Value::Dict(dict)
+}
+
+#[cfg(feature = "serde")]
+fn integer<T: TryInto<i64>>(v: T) -> Result<Value, Error> {
+    v.try_into().map(Value::Integer).map_err(|_| Error::Invalid { expected: "an integer within the range of i64" })
+}
+
+#[cfg(feature = "serde")]
+impl ser::Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    type SerializeSeq = ListSerializer;
+    type SerializeTuple = ListSerializer;
+    type SerializeTupleStruct = ListSerializer;
+    type SerializeTupleVariant = ListSerializer;
+    type SerializeMap = DictSerializer;
+    type SerializeStruct = DictSerializer;
+    type SerializeStructVariant = DictSerializer;
+
+    fn serialize_bool(self, v: bool) -> Result<Value, Error> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value, Error> {
+        integer(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value, Error> {
+        integer(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value, Error> {
+        integer(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value, Error> {
+        integer(v)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Value, Error> {
+        integer(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value, Error> {
+        integer(v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value, Error> {
+        integer(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value, Error> {
+        integer(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value, Error> {
+        integer(v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Value, Error> {
+        integer(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value, Error> {
+        Ok(Value::Float(v.into()))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value, Error> {
+        Ok(Value::Float(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value, Error> {
+        Ok(Value::from(v.encode_utf8(&mut [0; 4]) as &str))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, Error> {
+        Ok(Value::String(Bytes::copy_from_slice(v)))
+    }
+
+    fn serialize_none(self) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Value, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str)
+        -> Result<Value, Error>
+    {
+        Ok(Value::String(Bytes::from_static(variant.as_bytes())))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _name: &'static str, value: &T)
+        -> Result<Value, Error>
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(self, _name: &'static str, _index: u32,
+                                                        name: &'static str, value: &T)
+        -> Result<Value, Error>
+    {
+        Ok(variant(name, value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<ListSerializer, Error> {
+        Ok(ListSerializer { items: Vec::with_capacity(len.unwrap_or(0)), variant: None })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<ListSerializer, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<ListSerializer, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _index: u32, variant: &'static str, len: usize)
+        -> Result<ListSerializer, Error>
+    {
+        Ok(ListSerializer { items: Vec::with_capacity(len), variant: Some(variant) })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<DictSerializer, Error> {
+        Ok(DictSerializer { dict: BTreeMap::new(), key: None, variant: None })
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<DictSerializer, Error> {
+        self.serialize_map(None)
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _index: u32, variant: &'static str, _len: usize)
+        -> Result<DictSerializer, Error>
+    {
+        Ok(DictSerializer { dict: BTreeMap::new(), key: None, variant: Some(variant) })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl ListSerializer {
+    fn push<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.items.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Value, Error> {
+        let list = Value::List(self.items);
+
+        match self.variant {
+            Some(name) => Ok(variant(name, list)),
+            None => Ok(list),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl ser::SerializeSeq for ListSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.finish()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl ser::SerializeTuple for ListSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.finish()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl ser::SerializeTupleStruct for ListSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.finish()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl ser::SerializeTupleVariant for ListSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.finish()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl DictSerializer {
+    fn insert<T: ?Sized + Serialize>(&mut self, key: Bytes, value: &T) -> Result<(), Error> {
+        self.dict.insert(key, value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Value, Error> {
+        let dict = Value::Dict(self.dict);
+
+        match self.variant {
+            Some(name) => Ok(variant(name, dict)),
+            None => Ok(dict),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl ser::SerializeMap for DictSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Error> {
+        match key.serialize(ValueSerializer)? {
+            Value::String(key) => {
+                self.key = Some(key);
+                Ok(())
+            }
+            _ => Err(Error::Invalid { expected: "a string key" }),
+        }
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self.key.take().expect("serialize_value called before serialize_key");
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.finish()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl ser::SerializeStruct for DictSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> Result<(), Error> {
+        self.insert(Bytes::from_static(key.as_bytes()), value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.finish()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl ser::SerializeStructVariant for DictSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> Result<(), Error> {
+        self.insert(Bytes::from_static(key.as_bytes()), value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.finish()
+    }
+}
+
+// ===== impl Deserializer for Value =====
+
+// The variant of an enum, with its value if it has one
+#[cfg(feature = "serde")]
+struct Variant {
+    name: Value,
+    value: Option<Value>,
+}
+
+#[cfg(feature = "serde")]
+impl<'de> de::Deserializer<'de> for Value {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::String(s) => match str::from_utf8(&s) {
+                Ok(s) => visitor.visit_str(s),
+                Err(_) => visitor.visit_bytes(&s),
+            },
+            Value::Integer(n) => visitor.visit_i64(n),
+            Value::Float(n) => visitor.visit_f64(n),
+            Value::Bool(b) => visitor.visit_bool(b),
+            Value::Null => visitor.visit_unit(),
+            Value::Dict(dict) => {
+                let mut map = MapDeserializer::new(dict.into_iter().map(|(key, value)| (Value::String(key), value)));
+                let value = visitor.visit_map(&mut map)?;
+
+                map.end()?;
+                Ok(value)
+            }
+            Value::List(items) => {
+                let mut seq = SeqDeserializer::new(items.into_iter());
+                let value = visitor.visit_seq(&mut seq)?;
+
+                seq.end()?;
+                Ok(value)
+            }
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Null => visitor.visit_none(),
+            value => visitor.visit_some(value),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V)
+        -> Result<V::Value, Error>
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V)
+        -> Result<V::Value, Error>
+    {
+        let variant = match self {
+            Value::String(..) => Variant { name: self, value: None },
+            Value::Dict(dict) if dict.len() == 1 => {
+                let (name, value) = dict.into_iter().next().unwrap();
+                Variant { name: Value::String(name), value: Some(value) }
+            }
+            _ => return Err(Error::Invalid { expected: "an enum variant" }),
+        };
+
+        visitor.visit_enum(variant)
+    }
+
+    ::serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> IntoDeserializer<'de, Error> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> de::EnumAccess<'de> for Variant {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(mut self, seed: V) -> Result<(V::Value, Self), Error> {
+        let name = core::mem::replace(&mut self.name, Value::Null);
+        Ok((seed.deserialize(name)?, self))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> de::VariantAccess<'de> for Variant {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        match self.value {
+            None | Some(Value::Null) => Ok(()),
+            Some(_) => Err(Error::Invalid { expected: "a unit variant" }),
+        }
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        match self.value {
+            Some(value) => seed.deserialize(value),
+            None => Err(Error::Invalid { expected: "a newtype variant" }),
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            Some(value @ Value::List(..)) => de::Deserializer::deserialize_any(value, visitor),
+            _ => Err(Error::Invalid { expected: "a tuple variant" }),
+        }
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V)
+        -> Result<V::Value, Error>
+    {
+        match self.value {
+            Some(value @ Value::Dict(..)) => de::Deserializer::deserialize_any(value, visitor),
+            _ => Err(Error::Invalid { expected: "a struct variant" }),
+        }
+    }
+}
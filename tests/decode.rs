@@ -0,0 +1,856 @@
+#![cfg(feature = "tokio")]
+
+use bytes::{Bytes, BytesMut};
+use futures::{FutureExt, StreamExt};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio_netstring::{
+    decode_one, parse_iter, parse_iter_bytes, Builder, Event, EventDecoder, MemoryBudget, NetstringDecoder, NetstringError,
+    OversizePolicy, Parser, ReserveStrategy, Status,
+};
+use tokio_util::codec::Decoder;
+
+use std::io;
+
+// Decode every frame in `input`, stopping at the first error
+fn decode_all(decoder: &mut NetstringDecoder, input: &[u8]) -> (Vec<BytesMut>, Option<NetstringError>) {
+    let mut src = BytesMut::from(input);
+    let mut frames = Vec::new();
+
+    loop {
+        match decoder.decode(&mut src) {
+            Ok(Some(frame)) => frames.push(frame),
+            Ok(None) => return (frames, None),
+            Err(err) => return (frames, Some(err)),
+        }
+    }
+}
+
+fn assert_missing_terminator(err: Option<NetstringError>, expected: u8) {
+    match err {
+        Some(NetstringError::MissingTerminator { found, .. }) => assert_eq!(found, expected),
+        err => panic!("expected a missing terminator error, got {:?}", err),
+    }
+}
+
+#[test]
+fn decodes_well_formed_frames() {
+    let (frames, err) = decode_all(&mut NetstringDecoder::new(), b"5:hello,0:,5:world,");
+
+    assert!(err.is_none());
+    assert_eq!(frames, vec![&b"hello"[..], &b""[..], &b"world"[..]]);
+}
+
+#[test]
+fn rejects_length_one_short() {
+    let (frames, err) = decode_all(&mut NetstringDecoder::new(), b"4:hello,");
+
+    assert!(frames.is_empty());
+    assert_missing_terminator(err, b'o');
+}
+
+#[test]
+fn rejects_length_one_long() {
+    let (frames, err) = decode_all(&mut NetstringDecoder::new(), b"6:hello,5:world,");
+
+    assert!(frames.is_empty());
+    assert_missing_terminator(err, b'5');
+}
+
+#[test]
+fn waits_for_terminator() {
+    let mut decoder = NetstringDecoder::new();
+    let mut src = BytesMut::from(&b"5:hello"[..]);
+
+    assert!(decoder.decode(&mut src).unwrap().is_none());
+
+    src.extend_from_slice(b",");
+    assert_eq!(decoder.decode(&mut src).unwrap().unwrap(), &b"hello"[..]);
+}
+
+#[test]
+fn rejects_missing_comma() {
+    let (frames, err) = decode_all(&mut NetstringDecoder::new(), b"5:hello,5:world5:again,");
+
+    assert_eq!(frames, vec![&b"hello"[..]]);
+    assert_missing_terminator(err, b'5');
+}
+
+#[test]
+fn rejects_stray_byte() {
+    let (frames, err) = decode_all(&mut NetstringDecoder::new(), b"5:hello!,");
+
+    assert!(frames.is_empty());
+    assert_missing_terminator(err, b'!');
+}
+
+#[test]
+fn reports_non_printable_terminator() {
+    let mut decoder = Builder::new().length_field_offset(1).new_decoder();
+    let (frames, err) = decode_all(&mut decoder, b"\x015:hello\xff");
+
+    assert!(frames.is_empty());
+    assert_missing_terminator(err, 0xff);
+}
+
+fn assert_invalid_length(input: &[u8], offset: usize) {
+    let (frames, err) = decode_all(&mut NetstringDecoder::new(), input);
+
+    assert!(frames.is_empty());
+    assert_length_parse(err, input, offset);
+}
+
+fn assert_length_parse(err: Option<NetstringError>, input: &[u8], expected: usize) {
+    match err {
+        Some(NetstringError::LengthParse { offset, bytes }) => {
+            assert_eq!(offset, expected);
+            assert_eq!(bytes, &input[..=expected]);
+        }
+        err => panic!("expected a length parse error, got {:?}", err),
+    }
+}
+
+#[test]
+fn rejects_sloppy_lengths() {
+    assert_invalid_length(b"+5:hello,", 0);
+    assert_invalid_length(b"007:goldeny,", 1);
+    assert_invalid_length(b"00:,", 1);
+    assert_invalid_length(b" 5:hello,", 0);
+    assert_invalid_length(b"5 :hello,", 1);
+    assert_invalid_length(b":hello,", 0);
+    assert_invalid_length(b"-1:,", 0);
+}
+
+#[test]
+fn rejects_invalid_length_before_colon() {
+    // No `:` has arrived yet, the bad byte alone is enough to fail
+    assert_invalid_length(b"1x", 1);
+    assert_invalid_length(b"01", 1);
+}
+
+#[test]
+fn rejects_long_length_fields() {
+    let builder = Builder::new().max_length_digits(3).clone();
+
+    let (frames, err) = decode_all(&mut builder.new_decoder(), b"100:");
+    assert!(frames.is_empty());
+    assert!(err.is_none());
+
+    let (frames, err) = decode_all(&mut builder.new_decoder(), b"1000");
+    assert!(frames.is_empty());
+    assert_length_parse(err, b"1000", 3);
+}
+
+#[test]
+fn rejects_lengths_over_max_frame_length_early() {
+    let mut decoder = Builder::new().max_frame_length(99).new_decoder();
+
+    match decode_all(&mut decoder, b"100").1 {
+        Some(NetstringError::FrameTooLarge { declared: 100, max: 99 }) => {}
+        err => panic!("expected a frame too large error, got {:?}", err),
+    }
+}
+
+#[test]
+fn lenient_accepts_sloppy_lengths() {
+    let mut decoder = Builder::new().lenient(true).new_decoder();
+    let (frames, err) = decode_all(&mut decoder, b"+5:hello,007:goldeny, 5 :world,00:,");
+
+    assert!(err.is_none());
+    assert_eq!(frames, vec![&b"hello"[..], &b"goldeny"[..], &b"world"[..], &b""[..]]);
+}
+
+#[test]
+fn lenient_still_rejects_garbage() {
+    let (_, err) = decode_all(&mut Builder::new().lenient(true).new_decoder(), b"5x:hello,");
+    assert_length_parse(err, b"5x:hello,", 1);
+
+    let (_, err) = decode_all(&mut Builder::new().lenient(true).new_decoder(), b"1 2:hi,");
+    assert_length_parse(err, b"1 2:hi,", 2);
+}
+
+#[test]
+fn reports_unexpected_eof() {
+    let mut decoder = NetstringDecoder::new();
+    let mut src = BytesMut::from(&b"5:hel"[..]);
+
+    match decoder.decode_eof(&mut src) {
+        Err(NetstringError::UnexpectedEof { expected: Some(3), buffered: 5 }) => {}
+        res => panic!("expected an unexpected eof error, got {:?}", res),
+    }
+
+    let mut src = BytesMut::from(&b"12"[..]);
+
+    match NetstringDecoder::new().decode_eof(&mut src) {
+        Err(NetstringError::UnexpectedEof { expected: None, buffered: 2 }) => {}
+        res => panic!("expected an unexpected eof error, got {:?}", res),
+    }
+}
+
+#[test]
+fn decodes_without_the_codec_traits() {
+    let mut decoder = Builder::new().strip_frame(false).new_decoder();
+    let mut src = BytesMut::from(&b"5:hello,3:wor"[..]);
+
+    assert_eq!(decoder.decode_buf(&mut src).unwrap().unwrap(), &b"5:hello,"[..]);
+    assert!(decoder.decode_buf(&mut src).unwrap().is_none());
+
+    match decoder.decode_buf_eof(&mut src) {
+        Err(NetstringError::UnexpectedEof { expected: Some(1), buffered: 5 }) => {}
+        res => panic!("expected an unexpected eof error, got {:?}", res),
+    }
+}
+
+#[test]
+fn converts_through_io_error() {
+    let (_, err) = decode_all(&mut NetstringDecoder::new(), b"5:hello!");
+    let err: io::Error = err.unwrap().into();
+
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(err.to_string(), "missing terminator: expected `,`, found `!`");
+
+    match NetstringError::from(err) {
+        NetstringError::MissingTerminator { found: b'!', .. } => {}
+        err => panic!("expected a missing terminator error, got {:?}", err),
+    }
+
+    match NetstringError::from(io::Error::from(io::ErrorKind::BrokenPipe)) {
+        NetstringError::Io(err) => assert_eq!(err.kind(), io::ErrorKind::BrokenPipe),
+        err => panic!("expected an I/O error, got {:?}", err),
+    }
+}
+
+#[test]
+fn raw_mode_yields_one_frame_at_a_time() {
+    let mut decoder = Builder::new().strip_frame(false).new_decoder();
+    let (frames, err) = decode_all(&mut decoder, b"5:hello,0:,5:world,");
+
+    assert!(err.is_none());
+    assert_eq!(frames, vec![&b"5:hello,"[..], &b"0:,"[..], &b"5:world,"[..]]);
+}
+
+#[test]
+fn raw_mode_keeps_the_prefix() {
+    let mut decoder = Builder::new().strip_frame(false).length_field_offset(1).new_decoder();
+    let (frames, err) = decode_all(&mut decoder, b"\xff5:hello,\x015:world,\x02");
+
+    assert!(err.is_none());
+    assert_eq!(frames, vec![&b"\xff5:hello,"[..], &b"\x015:world,"[..]]);
+}
+
+#[test]
+fn raw_mode_waits_for_the_whole_frame() {
+    let mut decoder = Builder::new().strip_frame(false).new_decoder();
+    let mut src = BytesMut::new();
+
+    for &b in b"11:hello world" {
+        src.extend_from_slice(&[b]);
+        assert!(decoder.decode(&mut src).unwrap().is_none());
+    }
+
+    src.extend_from_slice(b",5:again,");
+    assert_eq!(decoder.decode(&mut src).unwrap().unwrap(), &b"11:hello world,"[..]);
+    assert_eq!(decoder.decode(&mut src).unwrap().unwrap(), &b"5:again,"[..]);
+    assert!(src.is_empty());
+}
+
+#[test]
+fn raw_mode_validates_the_terminator() {
+    let mut decoder = Builder::new().strip_frame(false).new_decoder();
+    let (frames, err) = decode_all(&mut decoder, b"5:hello,5:world!");
+
+    assert_eq!(frames, vec![&b"5:hello,"[..]]);
+    assert_missing_terminator(err, b'!');
+}
+
+#[test]
+fn strip_mode_leaves_pipelined_frames_buffered() {
+    let mut decoder = Builder::new().length_field_offset(1).new_decoder();
+    let mut src = BytesMut::from(&b"\xff5:hello,\x015:wor"[..]);
+
+    assert_eq!(decoder.decode(&mut src).unwrap().unwrap(), &b"hello"[..]);
+    assert_eq!(src, &b"\x015:wor"[..]);
+    assert!(decoder.decode(&mut src).unwrap().is_none());
+
+    src.extend_from_slice(b"ld,");
+    assert_eq!(decoder.decode(&mut src).unwrap().unwrap(), &b"world"[..]);
+}
+
+#[test]
+fn frame_decoder_reports_prefix_and_offsets() {
+    let mut decoder = Builder::new().length_field_offset(2).lenient(true).new_frame_decoder();
+    let mut src = BytesMut::from(&b"v15:hello,v2 005:world,v3"[..]);
+
+    let frame = decoder.decode(&mut src).unwrap().unwrap();
+    assert_eq!(frame.prefix(), &b"v1"[..]);
+    assert_eq!(frame.payload(), &b"hello"[..]);
+    assert_eq!(frame.len(), 5);
+    assert_eq!(frame.offset(), 0);
+
+    let frame = decoder.decode(&mut src).unwrap().unwrap();
+    assert_eq!(frame.prefix(), &b"v2"[..]);
+    assert_eq!(frame.payload(), &b"world"[..]);
+    assert_eq!(frame.offset(), 10);
+
+    assert!(decoder.decode(&mut src).unwrap().is_none());
+    src.extend_from_slice(b"0:,");
+
+    let frame = decoder.decode(&mut src).unwrap().unwrap();
+    assert_eq!(frame.prefix(), &b"v3"[..]);
+    assert!(frame.is_empty());
+    assert_eq!(frame.offset(), 23);
+}
+
+#[test]
+fn parses_heads_trickling_in() {
+    let mut decoder = Builder::new().lenient(true).length_field_offset(1).new_decoder();
+    let mut src = BytesMut::new();
+
+    for &b in b"\x01 +0012 :" {
+        assert!(decoder.decode(&mut src).unwrap().is_none());
+        src.extend_from_slice(&[b]);
+    }
+
+    src.extend_from_slice(b"hello world!,\x02");
+    assert_eq!(decoder.decode(&mut src).unwrap().unwrap(), &b"hello world!"[..]);
+
+    for &b in b"3:abc," {
+        assert!(decoder.decode(&mut src).unwrap().is_none());
+        src.extend_from_slice(&[b]);
+    }
+
+    assert_eq!(decoder.decode(&mut src).unwrap().unwrap(), &b"abc"[..]);
+}
+
+#[test]
+fn rejects_invalid_heads_trickling_in() {
+    let mut decoder = NetstringDecoder::new();
+    let mut src = BytesMut::new();
+
+    for &b in b"123" {
+        src.extend_from_slice(&[b]);
+        assert!(decoder.decode(&mut src).unwrap().is_none());
+    }
+
+    src.extend_from_slice(b"x");
+    assert_length_parse(decoder.decode(&mut src).err(), b"123x", 3);
+}
+
+#[test]
+fn eager_reserve_grows_for_the_whole_frame() {
+    let mut decoder = Builder::new().reserve_strategy(ReserveStrategy::Eager).new_decoder();
+    let mut src = BytesMut::from(&b"8388608:"[..]);
+
+    assert!(decoder.decode(&mut src).unwrap().is_none());
+    assert!(src.capacity() >= 8_388_617);
+}
+
+#[test]
+fn incremental_reserve_grows_as_data_arrives() {
+    let strategy = ReserveStrategy::Incremental { chunk: 1024 };
+    let mut decoder = Builder::new().reserve_strategy(strategy).new_decoder();
+    let mut src = BytesMut::from(&b"8388608:"[..]);
+
+    assert!(decoder.decode(&mut src).unwrap().is_none());
+    assert!(src.capacity() < 64 * 1024);
+
+    src.extend_from_slice(&[b'x'; 4096]);
+    assert!(decoder.decode(&mut src).unwrap().is_none());
+    assert!(src.capacity() >= 8 + 4096 + 1024);
+    assert!(src.capacity() < 64 * 1024);
+}
+
+#[test]
+fn releases_the_buffer_after_a_large_frame() {
+    let mut decoder = NetstringDecoder::new();
+    let mut src = BytesMut::new();
+
+    src.extend_from_slice(b"1000000:");
+    src.extend_from_slice(&vec![b'x'; 1_000_000]);
+    src.extend_from_slice(b",5:hel");
+
+    let frame = decoder.decode(&mut src).unwrap().unwrap();
+    assert_eq!(frame.len(), 1_000_000);
+    assert_eq!(src, &b"5:hel"[..]);
+    assert!(src.capacity() < 64 * 1024);
+
+    src.extend_from_slice(b"lo,");
+    assert_eq!(decoder.decode(&mut src).unwrap().unwrap(), &b"hello"[..]);
+}
+
+#[tokio::test]
+async fn waits_for_the_memory_budget() {
+    let budget = MemoryBudget::new(8);
+    let builder = Builder::new().memory_budget(budget.clone()).clone();
+
+    // The first reader holds the whole budget while its frame trickles in
+    let (mut tx, rx) = tokio::io::duplex(64);
+    tx.write_all(b"5:he").await.unwrap();
+
+    let mut first = builder.new_read(rx);
+    assert!(first.next().now_or_never().is_none());
+    assert_eq!(budget.available(), 0);
+
+    // The second one cannot grow its buffer until the memory is released
+    let mut second = builder.new_read(&b"2:hi,"[..]);
+    assert!(second.next().now_or_never().is_none());
+
+    drop(first);
+    assert_eq!(budget.used(), 0);
+    assert_eq!(second.next().await.unwrap().unwrap(), &b"hi"[..]);
+}
+
+#[tokio::test]
+async fn never_grows_the_read_buffer_past_the_memory_budget() {
+    let budget = MemoryBudget::new(10_000);
+    let (mut tx, rx) = tokio::io::duplex(64 * 1024);
+    let mut frames = Builder::new().memory_budget(budget.clone()).new_read(rx);
+
+    let mut input = b"9000:".to_vec();
+    input.resize(input.len() + 9000, b'x');
+    input.push(b',');
+
+    // Feed the frame in parts, the buffer grows with it
+    for part in input.chunks(1000) {
+        assert!(frames.next().now_or_never().is_none());
+        assert!(budget.used() <= 10_000, "buffer grew to {} bytes", budget.used());
+
+        tx.write_all(part).await.unwrap();
+    }
+
+    assert_eq!(frames.next().await.unwrap().unwrap().len(), 9000);
+    assert!(budget.used() <= 10_000);
+}
+
+#[tokio::test]
+async fn rejects_frames_larger_than_the_memory_budget() {
+    let budget = MemoryBudget::new(8);
+    let builder = Builder::new().memory_budget(budget.clone()).clone();
+
+    // Head and terminator included, 5 payload bytes fit in the budget
+    let mut frames = builder.new_read(&b"5:hello,6:hello!,"[..]);
+    assert_eq!(frames.next().await.unwrap().unwrap(), &b"hello"[..]);
+
+    match frames.next().await {
+        Some(Err(NetstringError::FrameTooLarge { declared: 6, max: 5 })) => {}
+        res => panic!("expected a frame too large error, got {:?}", res),
+    }
+
+    assert!(frames.next().await.is_none());
+    drop(frames);
+    assert_eq!(budget.used(), 0);
+}
+
+// Decode every event in `input`, fed `step` bytes at a time
+fn decode_events(decoder: &mut EventDecoder, input: &[u8], step: usize) -> Result<Vec<Event>, NetstringError> {
+    let mut src = BytesMut::new();
+    let mut events = Vec::new();
+
+    for chunk in input.chunks(step) {
+        src.extend_from_slice(chunk);
+
+        while let Some(event) = decoder.decode(&mut src)? {
+            events.push(event);
+        }
+    }
+
+    while let Some(event) = decoder.decode_eof(&mut src)? {
+        events.push(event);
+    }
+
+    Ok(events)
+}
+
+#[test]
+fn skips_oversized_frames() {
+    let builder = Builder::new().max_frame_length(5).on_oversize(OversizePolicy::Skip).clone();
+
+    for step in [1, 4, 64] {
+        let events = decode_events(&mut builder.new_event_decoder(), b"11:hello world,2:hi,", step).unwrap();
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0], Event::Skipped { offset: 0, len: 11 });
+
+        match events[1] {
+            Event::Frame(ref frame) => {
+                assert_eq!(frame.payload(), &b"hi"[..]);
+                assert_eq!(frame.offset(), 15);
+            }
+            ref event => panic!("expected a frame, got {:?}", event),
+        }
+    }
+
+    // The plain decoder drops the oversized frame
+    let (frames, err) = decode_all(&mut builder.new_decoder(), b"11:hello world,2:hi,");
+    assert!(err.is_none());
+    assert_eq!(frames, vec![&b"hi"[..]]);
+}
+
+#[test]
+fn skips_oversized_frames_without_buffering_them() {
+    let mut decoder = Builder::new().max_frame_length(5).on_oversize(OversizePolicy::Skip).new_event_decoder();
+    let mut src = BytesMut::from(&b"8388608:"[..]);
+
+    for _ in 0..1024 {
+        src.extend_from_slice(&[b'x'; 8192]);
+        assert!(decoder.decode(&mut src).unwrap().is_none());
+        assert!(src.is_empty());
+    }
+
+    src.extend_from_slice(b",");
+    assert_eq!(decoder.decode(&mut src).unwrap(), Some(Event::Skipped { offset: 0, len: 8_388_608 }));
+}
+
+#[test]
+fn streams_oversized_frames() {
+    let builder = Builder::new().max_frame_length(5).on_oversize(OversizePolicy::Stream).clone();
+
+    for step in [1, 4, 64] {
+        let events = decode_events(&mut builder.new_event_decoder(), b"11:hello world,2:hi,", step).unwrap();
+        let mut payload = Vec::new();
+
+        for event in &events[..events.len() - 1] {
+            match *event {
+                Event::Chunk { offset: 0, len: 11, ref data, last } => {
+                    payload.extend_from_slice(data);
+                    assert_eq!(last, event == &events[events.len() - 2]);
+                }
+                ref event => panic!("expected a chunk, got {:?}", event),
+            }
+        }
+
+        assert_eq!(payload, b"hello world");
+        assert!(matches!(events.last(), Some(Event::Frame(frame)) if frame.payload() == &b"hi"[..]));
+    }
+}
+
+#[test]
+fn rejects_frames_to_stream_without_events() {
+    let builder = Builder::new().max_frame_length(5).on_oversize(OversizePolicy::Stream).clone();
+    let input = b"2:hi,11:hello world,2:hi,";
+
+    let assert_too_large = |err: Option<NetstringError>| match err {
+        Some(NetstringError::FrameTooLarge { declared: 11, max: 5 }) => {}
+        err => panic!("expected a frame too large error, got {:?}", err),
+    };
+
+    let (frames, err) = decode_all(&mut builder.new_decoder(), input);
+    assert_eq!(frames, vec![&b"hi"[..]]);
+    assert_too_large(err);
+
+    let mut decoder = builder.new_frame_decoder();
+    let mut src = BytesMut::from(&input[..]);
+    assert_eq!(decoder.decode(&mut src).unwrap().unwrap().payload(), &b"hi"[..]);
+    assert_too_large(decoder.decode(&mut src).err());
+
+    let mut frames = builder.new_parse_iter(input);
+    assert_eq!(frames.next().unwrap().unwrap(), b"hi");
+    assert_too_large(frames.next().unwrap().err());
+    assert!(frames.next().is_none());
+}
+
+#[test]
+fn rejects_oversized_frames_missing_terminator() {
+    for policy in [OversizePolicy::Skip, OversizePolicy::Stream] {
+        let mut decoder = Builder::new().max_frame_length(5).on_oversize(policy).new_event_decoder();
+        let mut src = BytesMut::from(&b"11:hello world!"[..]);
+
+        assert_missing_terminator(decoder.decode(&mut src).err(), b'!');
+    }
+}
+
+#[test]
+fn reports_eof_within_oversized_frames() {
+    let builder = Builder::new().max_frame_length(5).on_oversize(OversizePolicy::Skip).clone();
+
+    for input in [&b"11:hello"[..], b"11:hello world"] {
+        match decode_events(&mut builder.new_event_decoder(), input, 64) {
+            Err(NetstringError::UnexpectedEof { expected: Some(expected), buffered: 0 }) => {
+                assert_eq!(expected, 15 - input.len());
+            }
+            res => panic!("expected an unexpected EOF error, got {:?}", res),
+        }
+    }
+}
+
+#[test]
+fn rejects_lengths_too_large_to_skip() {
+    let mut decoder = Builder::new().max_length_digits(30).on_oversize(OversizePolicy::Skip).new_event_decoder();
+    let mut src = BytesMut::from(&b"99999999999999999999999:"[..]);
+
+    match decoder.decode(&mut src) {
+        Err(NetstringError::FrameTooLarge { declared: u64::MAX, .. }) => {}
+        res => panic!("expected a frame too large error, got {:?}", res),
+    }
+}
+
+#[tokio::test]
+async fn reads_bodies_one_frame_at_a_time() {
+    let (mut tx, rx) = tokio::io::duplex(16);
+    let mut bodies = Builder::new().new_body_read(rx);
+
+    let writer = tokio::spawn(async move {
+        tx.write_all(b"26:abcdefghijklmnopqrstuvwxyz,0:,").await.unwrap();
+    });
+
+    let mut body = bodies.next().await.unwrap().unwrap();
+    assert_eq!(body.len(), 26);
+
+    // The next frame waits for the body to be read
+    assert!(bodies.next().now_or_never().is_none());
+
+    let mut payload = Vec::new();
+    body.read_to_end(&mut payload).await.unwrap();
+    assert_eq!(payload, b"abcdefghijklmnopqrstuvwxyz");
+
+    let body = bodies.next().await.unwrap().unwrap();
+    assert!(body.is_empty());
+    drop(body);
+
+    writer.await.unwrap();
+    assert!(bodies.next().await.is_none());
+}
+
+#[tokio::test]
+async fn discards_dropped_bodies() {
+    let mut bodies = Builder::new().new_body_read(&b"11:hello world,2:hi,"[..]);
+
+    let mut body = bodies.next().await.unwrap().unwrap();
+    let mut partial = [0; 5];
+    body.read_exact(&mut partial).await.unwrap();
+    assert_eq!(&partial, b"hello");
+    drop(body);
+
+    let mut body = bodies.next().await.unwrap().unwrap();
+    let mut payload = Vec::new();
+    body.read_to_end(&mut payload).await.unwrap();
+    assert_eq!(payload, b"hi");
+}
+
+#[tokio::test]
+async fn checks_the_terminator_after_the_body() {
+    let mut bodies = Builder::new().new_body_read(&b"5:hello!"[..]);
+    let mut body = bodies.next().await.unwrap().unwrap();
+
+    let mut payload = Vec::new();
+    let err = body.read_to_end(&mut payload).await.unwrap_err();
+    assert_eq!(payload, b"hello");
+    assert_missing_terminator(Some(err.into()), b'!');
+
+    drop(body);
+    assert!(bodies.next().await.is_none());
+
+    // A dropped body is checked by the stream
+    let mut bodies = Builder::new().new_body_read(&b"5:hello!"[..]);
+    drop(bodies.next().await.unwrap().unwrap());
+    assert_missing_terminator(bodies.next().await.unwrap().err(), b'!');
+}
+
+#[tokio::test]
+async fn reports_eof_within_bodies() {
+    let mut bodies = Builder::new().new_body_read(&b"5:hel"[..]);
+    let mut body = bodies.next().await.unwrap().unwrap();
+
+    let err = body.read_to_end(&mut Vec::new()).await.unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+    match NetstringError::from(err) {
+        NetstringError::UnexpectedEof { expected: Some(3), buffered: 0 } => {}
+        err => panic!("expected an unexpected EOF error, got {:?}", err),
+    }
+}
+
+fn assert_corrupt(err: Option<NetstringError>, expected_offset: u64, expected_skipped: u64) {
+    match err {
+        Some(NetstringError::Corrupt { offset, skipped }) => {
+            assert_eq!((offset, skipped), (expected_offset, expected_skipped));
+        }
+        err => panic!("expected a corrupt error, got {:?}", err),
+    }
+}
+
+#[test]
+fn resyncs_after_corrupt_frames() {
+    let builder = Builder::new().resync(true).clone();
+    // The `8:` in the middle looks like a head, until its terminator is
+    // checked
+    let input = b"5:hello,x1:a,3:abcd,8:wxyz,2:hi,";
+
+    for step in [1, 3, 64] {
+        let mut decoder = builder.new_frame_decoder();
+        let mut src = BytesMut::new();
+        let mut items = Vec::new();
+
+        for chunk in input.chunks(step) {
+            src.extend_from_slice(chunk);
+
+            loop {
+                match decoder.decode(&mut src) {
+                    Ok(Some(frame)) => items.push(Ok((frame.offset(), frame.into_payload()))),
+                    Ok(None) => break,
+                    Err(err) => items.push(Err(err)),
+                }
+            }
+        }
+
+        assert_eq!(items.len(), 5);
+
+        let mut items = items.into_iter();
+        assert_eq!(items.next().unwrap().unwrap(), (0, "hello".into()));
+        assert_corrupt(items.next().unwrap().err(), 8, 1);
+        assert_eq!(items.next().unwrap().unwrap(), (9, "a".into()));
+        assert_corrupt(items.next().unwrap().err(), 13, 14);
+        assert_eq!(items.next().unwrap().unwrap(), (27, "hi".into()));
+    }
+}
+
+#[test]
+fn resyncs_on_invalid_lengths_and_large_frames() {
+    let mut decoder = Builder::new().resync(true).max_frame_length(5).new_decoder();
+    let mut src = BytesMut::from(&b"1x:a,99:,2:ok,"[..]);
+
+    assert_corrupt(decoder.decode(&mut src).err(), 0, 9);
+    assert_eq!(decoder.decode(&mut src).unwrap().unwrap(), &b"ok"[..]);
+}
+
+#[test]
+fn discards_the_rest_of_the_stream_at_eof_when_resyncing() {
+    let mut decoder = Builder::new().resync(true).new_decoder();
+    let mut src = BytesMut::from(&b"5:hello!5:wor"[..]);
+
+    assert!(decoder.decode(&mut src).unwrap().is_none());
+    assert_corrupt(decoder.decode_eof(&mut src).err(), 0, 13);
+    assert!(src.is_empty());
+    assert!(decoder.decode_eof(&mut src).unwrap().is_none());
+}
+
+#[tokio::test]
+async fn keeps_reading_after_corrupt_frames() {
+    let io: &[u8] = b"5:hello,junk5:world,garbage";
+    let items: Vec<_> = Builder::new().resync(true).new_read(io).collect().await;
+
+    assert_eq!(items.len(), 4);
+    assert_eq!(items[0].as_ref().unwrap(), &b"hello"[..]);
+    assert!(matches!(items[1], Err(NetstringError::Corrupt { offset: 8, skipped: 4 })));
+    assert_eq!(items[2].as_ref().unwrap(), &b"world"[..]);
+    assert!(matches!(items[3], Err(NetstringError::Corrupt { offset: 20, skipped: 7 })));
+
+    // Without resync, the stream ends at the first error
+    let items: Vec<_> = Builder::new().new_read(io).collect().await;
+    assert_eq!(items.len(), 2);
+}
+
+#[test]
+fn parser_reports_how_many_bytes_are_missing() {
+    let mut parser = Parser::new();
+    let input = b"11:hello world,";
+
+    assert!(matches!(parser.parse(b""), Status::NeedMore(1)));
+    assert!(matches!(parser.parse(b"11"), Status::NeedMore(1)));
+    assert!(matches!(parser.parse(b"11:hel"), Status::NeedMore(9)));
+    assert!(matches!(parser.parse(&input[..14]), Status::NeedMore(1)));
+    assert!(matches!(parser.parse(input), Status::Frame(payload) if payload == (3..14)));
+
+    // Ready for the next frame
+    assert!(matches!(parser.parse(b"0:,"), Status::Frame(payload) if payload == (2..2)));
+}
+
+#[test]
+fn parser_reports_errors_and_oversized_frames() {
+    let mut parser = Parser::new();
+
+    match parser.parse(b"5:hello!") {
+        Status::Error(NetstringError::MissingTerminator { found: b'!', .. }) => {}
+        status => panic!("expected a missing terminator error, got {:?}", status),
+    }
+
+    // Errors reset the parser
+    assert!(matches!(parser.parse(b"2:hi,"), Status::Frame(_)));
+
+    let mut parser = Builder::new().max_frame_length(4).on_oversize(OversizePolicy::Skip).new_parser();
+    assert!(matches!(parser.parse(b"10:0123"), Status::Oversize { head: 3, len: 10 }));
+    assert!(matches!(parser.parse(b"4:abcd,"), Status::Frame(_)));
+}
+
+#[test]
+fn iterates_over_slices() {
+    let input = b"5:hello,0:,11:hello world,";
+    let frames: Vec<_> = parse_iter(input).map(Result::unwrap).collect();
+    assert_eq!(frames, [&b"hello"[..], b"", b"hello world"]);
+
+    let mut frames = parse_iter(b"5:hello,5:wor");
+    assert_eq!(frames.next().unwrap().unwrap(), b"hello");
+    assert_eq!(frames.remainder(), b"5:wor");
+
+    match frames.next() {
+        Some(Err(NetstringError::UnexpectedEof { expected: Some(3), buffered: 5 })) => {}
+        res => panic!("expected an unexpected eof error, got {:?}", res),
+    }
+
+    assert!(frames.next().is_none());
+}
+
+#[test]
+fn iterates_over_shared_bytes() {
+    let buf = Bytes::from(b"5:hello,5:world,".to_vec());
+    let frames: Vec<_> = parse_iter_bytes(buf.clone()).map(Result::unwrap).collect();
+
+    assert_eq!(frames, ["hello", "world"]);
+
+    // The payloads point into the original buffer
+    assert_eq!(frames[0].as_ptr(), buf[2..].as_ptr());
+    assert_eq!(frames[1].as_ptr(), buf[10..].as_ptr());
+}
+
+#[test]
+fn iterators_validate_like_the_decoder() {
+    let mut frames = parse_iter(b"5:hello!5:world,");
+    assert_missing_terminator(frames.next().unwrap().err(), b'!');
+    assert!(frames.next().is_none());
+
+    let builder = Builder::new().max_frame_length(5).on_oversize(OversizePolicy::Skip).resync(true).clone();
+    let input = b"5:hello,10:0123456789,junk5:world,";
+
+    let items: Vec<_> = builder.new_parse_iter(input).collect();
+    assert_eq!(items.len(), 3);
+    assert_eq!(items[0].as_ref().unwrap(), b"hello");
+    assert!(matches!(items[1], Err(NetstringError::Corrupt { offset: 22, skipped: 4 })));
+    assert_eq!(items[2].as_ref().unwrap(), b"world");
+
+    let events = decode_events(&mut builder.new_event_decoder(), input, input.len());
+    assert!(matches!(events, Err(NetstringError::Corrupt { offset: 22, skipped: 4 })));
+}
+
+#[test]
+fn decodes_one_frame() {
+    let (payload, rest) = decode_one(b"0:,5:hello,").unwrap();
+    assert_eq!(payload, b"");
+    assert_eq!(rest, b"5:hello,");
+
+    match decode_one(b"") {
+        Err(NetstringError::UnexpectedEof { expected: None, buffered: 0 }) => {}
+        res => panic!("expected an unexpected eof error, got {:?}", res),
+    }
+
+    match decode_one(b"5:hel") {
+        Err(NetstringError::UnexpectedEof { expected: Some(3), buffered: 5 }) => {}
+        res => panic!("expected an unexpected eof error, got {:?}", res),
+    }
+
+    assert_missing_terminator(decode_one(b"5:hello;").err(), b';');
+}
+
+#[test]
+fn decodes_one_frame_over_the_default_limit() {
+    let payload = vec![b'x'; 32 * 1024 * 1024 + 1];
+    let frame = tokio_netstring::encode(&payload);
+
+    match decode_one(&frame) {
+        Err(NetstringError::FrameTooLarge { declared: 33_554_433, max: 33_554_432 }) => {}
+        res => panic!("expected a frame too large error, got {:?}", res.map(|_| ())),
+    }
+
+    let (decoded, rest) = Builder::new().max_frame_length(payload.len()).decode_one(&frame).unwrap();
+    assert_eq!(decoded, &payload[..]);
+    assert!(rest.is_empty());
+}
@@ -0,0 +1,397 @@
+#![cfg(feature = "tokio")]
+
+use bytes::BytesMut;
+use futures::SinkExt;
+
+use tokio::io::AsyncWrite;
+use tokio_netstring::{
+    encode as encode_frame, encode_into, encoded_len, Builder, MemoryBudget, NetstringEncoder, NetstringError, Prefixed,
+};
+use tokio_util::codec::Encoder;
+
+use std::io::{self, IoSlice};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+// Records every write call made by the transport
+#[derive(Default)]
+struct Recorder {
+    writes: Vec<Vec<u8>>,
+
+    // Maximum number of bytes accepted by a single write
+    limit: Option<usize>,
+}
+
+impl AsyncWrite for Recorder {
+    fn poll_write(mut self: Pin<&mut Self>, _: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        self.writes.push(buf.to_vec());
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_write_vectored(mut self: Pin<&mut Self>, _: &mut Context<'_>, bufs: &[IoSlice<'_>])
+        -> Poll<io::Result<usize>>
+    {
+        let mut write = bufs.iter().flat_map(|b| b.iter().copied()).collect::<Vec<_>>();
+        write.truncate(self.limit.unwrap_or(usize::MAX));
+        let n = write.len();
+        self.writes.push(write);
+        Poll::Ready(Ok(n))
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        true
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+fn encode(encoder: &mut NetstringEncoder, payload: &[u8]) -> Result<BytesMut, NetstringError> {
+    let mut dst = BytesMut::new();
+    encoder.encode(payload, &mut dst)?;
+    Ok(dst)
+}
+
+#[test]
+fn encodes_lengths() {
+    let mut encoder = NetstringEncoder::new();
+
+    assert_eq!(encode(&mut encoder, b"").unwrap(), &b"0:,"[..]);
+    assert_eq!(encode(&mut encoder, b"hello").unwrap(), &b"5:hello,"[..]);
+    assert_eq!(encode(&mut encoder, b"0123456789").unwrap(), &b"10:0123456789,"[..]);
+
+    let payload = vec![b'x'; 1_000_000];
+    let frame = encode(&mut encoder, &payload).unwrap();
+    assert_eq!(&frame[..8], b"1000000:");
+    assert_eq!(frame.len(), 8 + 1_000_000 + 1);
+}
+
+#[test]
+fn encodes_prefix() {
+    let mut encoder = Builder::new().length_field_offset(2).write_prefix(&b"v1"[..]).new_encoder();
+
+    assert_eq!(encode(&mut encoder, b"hello").unwrap(), &b"v15:hello,"[..]);
+}
+
+#[test]
+fn checks_prefixes_against_the_length_field_offset() {
+    let mut encoder = Builder::new().length_field_offset(1).write_prefix(&b"v1"[..]).new_encoder();
+
+    match encode(&mut encoder, b"hello") {
+        Err(NetstringError::PrefixLength { len: 2, expected: 1 }) => {}
+        res => panic!("expected a prefix length error, got {:?}", res),
+    }
+
+    // Without a prefix, the length field offset only applies to decoding
+    let mut encoder = Builder::new().length_field_offset(1).new_encoder();
+    assert_eq!(encode(&mut encoder, b"hello").unwrap(), &b"5:hello,"[..]);
+
+    let mut dst = BytesMut::new();
+    assert!(encoder.encode(Prefixed::new(&b"ab"[..], &b"hello"[..]), &mut dst).is_err());
+    assert!(dst.is_empty());
+}
+
+#[tokio::test]
+async fn round_trips_prefixes() {
+    use futures::TryStreamExt;
+    use tokio_util::codec::{FramedRead, FramedWrite};
+
+    let mut builder = Builder::new();
+    builder.length_field_offset(2).write_prefix(b"v1".to_vec());
+
+    let mut buf = Vec::new();
+    let mut writer = builder.new_write(&mut buf);
+    writer.send(&b"hello"[..]).await.unwrap();
+    writer.send(&b""[..]).await.unwrap();
+
+    let frames: Vec<_> = builder.new_frame_read(&buf[..]).try_collect().await.unwrap();
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].prefix(), &b"v1"[..]);
+    assert_eq!(frames[0].payload(), &b"hello"[..]);
+    assert_eq!(frames[1].prefix(), &b"v1"[..]);
+    assert!(frames[1].is_empty());
+
+    let payloads: Vec<_> = builder.new_read(&buf[..]).try_collect().await.unwrap();
+    assert_eq!(payloads, [&b"hello"[..], b""]);
+
+    // Each frame can carry a prefix of its own
+    let mut buf = Vec::new();
+    let mut writer = FramedWrite::new(&mut buf, builder.new_encoder());
+    writer.send(Prefixed::new(&b"v2"[..], &b"hello"[..])).await.unwrap();
+    writer.send(Prefixed::new(&b"v3"[..], &b"world"[..])).await.unwrap();
+    assert_eq!(buf, b"v25:hello,v35:world,");
+
+    let frames: Vec<_> = FramedRead::new(&buf[..], builder.new_frame_decoder()).try_collect().await.unwrap();
+    assert_eq!(frames[0].prefix(), &b"v2"[..]);
+    assert_eq!(frames[0].payload(), &b"hello"[..]);
+    assert_eq!(frames[1].prefix(), &b"v3"[..]);
+    assert_eq!(frames[1].payload(), &b"world"[..]);
+}
+
+#[test]
+fn rejects_large_frames() {
+    let mut encoder = Builder::new().max_frame_length(4).new_encoder();
+
+    match encode(&mut encoder, b"hello") {
+        Err(NetstringError::FrameTooLarge { declared: 5, max: 4 }) => {}
+        res => panic!("expected a frame too large error, got {:?}", res),
+    }
+}
+
+#[test]
+fn encodes_into_any_buf_mut() {
+    let encoder = Builder::new().length_field_offset(2).write_prefix(&b"v1"[..]).new_encoder();
+
+    let mut vec = Vec::new();
+    encoder.encode_buf(&b"hello"[..], &mut vec).unwrap();
+    encoder.encode_buf(&b""[..], &mut vec).unwrap();
+    assert_eq!(vec, b"v15:hello,v10:,");
+
+    let mut array = [0; 16];
+    let mut slice = &mut array[..];
+    encoder.encode_buf(&b"abc"[..], &mut slice).unwrap();
+    assert_eq!(slice.len(), 8);
+    assert_eq!(&array[..8], b"v13:abc,");
+}
+
+#[test]
+fn one_shot_helpers_match_the_encoder() {
+    let mut encoder = NetstringEncoder::new();
+
+    for len in [0, 1, 9, 10, 99, 100, 12_345] {
+        let payload = vec![b'x'; len];
+        let frame = encode_frame(&payload);
+
+        assert_eq!(frame, encode(&mut encoder, &payload).unwrap());
+        assert_eq!(frame.len(), encoded_len(len));
+    }
+
+    const LEN: usize = encoded_len(100);
+    let mut array = [0; LEN + 1];
+    encode_into(&[b'y'; 100], &mut &mut array[..]);
+    assert_eq!(&array[..4], b"100:");
+    assert_eq!(&array[LEN - 1..], b",\0");
+}
+
+#[cfg(feature = "futures-io")]
+#[tokio::test]
+async fn frames_futures_io_types() {
+    use futures::io::Cursor;
+    use futures::StreamExt;
+
+    let mut writer = Builder::new().new_futures_write(Cursor::new(Vec::new()));
+    writer.send(&b"hello"[..]).await.unwrap();
+    writer.send(&b"world"[..]).await.unwrap();
+
+    let buf = writer.into_inner().into_inner().into_inner();
+    assert_eq!(buf, b"5:hello,5:world,");
+
+    let frames: Vec<_> = Builder::new().new_futures_read(Cursor::new(buf)).collect().await;
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[1].as_ref().unwrap(), &b"world"[..]);
+}
+
+#[tokio::test]
+async fn writes_each_frame_in_one_call() {
+    let mut writer = Builder::new().length_field_offset(1).write_prefix(&b"\x01"[..]).new_write(Recorder::default());
+
+    writer.send(&b"hello"[..]).await.unwrap();
+    writer.send(&b"world!"[..]).await.unwrap();
+
+    assert_eq!(writer.get_ref().writes, vec![b"\x015:hello,".to_vec(), b"\x016:world!,".to_vec()]);
+}
+
+#[tokio::test]
+async fn coalesces_queued_frames() {
+    let mut writer = Builder::new().new_write(Recorder::default());
+
+    writer.feed(&b"a"[..]).await.unwrap();
+    writer.feed(&b"bc"[..]).await.unwrap();
+    writer.feed(&b""[..]).await.unwrap();
+    assert!(writer.get_ref().writes.is_empty());
+
+    writer.flush().await.unwrap();
+    assert_eq!(writer.get_ref().writes, vec![b"1:a,2:bc,0:,".to_vec()]);
+}
+
+#[tokio::test]
+async fn applies_backpressure_at_the_high_water_mark() {
+    let mut writer = Builder::new().max_queued_frames(2).new_write(Recorder::default());
+
+    writer.feed(&b"a"[..]).await.unwrap();
+    writer.feed(&b"b"[..]).await.unwrap();
+    assert!(writer.get_ref().writes.is_empty());
+
+    // The queue is full, the next frame waits for the first two to be written
+    writer.feed(&b"c"[..]).await.unwrap();
+    assert_eq!(writer.get_ref().writes, vec![b"1:a,1:b,".to_vec()]);
+
+    writer.flush().await.unwrap();
+    assert_eq!(writer.get_ref().writes, vec![b"1:a,1:b,".to_vec(), b"1:c,".to_vec()]);
+
+    let mut writer = Builder::new().write_buffer_bytes(8).new_write(Recorder::default());
+
+    writer.feed(&b"abc"[..]).await.unwrap();
+    writer.feed(&b"def"[..]).await.unwrap();
+    writer.feed(&b"g"[..]).await.unwrap();
+    assert_eq!(writer.get_ref().writes, vec![b"3:abc,3:def,".to_vec()]);
+}
+
+#[tokio::test]
+async fn resumes_partial_writes_across_frames() {
+    let recorder = Recorder { limit: Some(3), ..Recorder::default() };
+    let mut writer = Builder::new().new_write(recorder);
+
+    writer.feed(&b"hello"[..]).await.unwrap();
+    writer.feed(&b"world"[..]).await.unwrap();
+    writer.flush().await.unwrap();
+
+    let written = writer.get_ref().writes.concat();
+    assert_eq!(written, b"5:hello,5:world,");
+    assert!(writer.get_ref().writes.iter().all(|w| w.len() <= 3));
+}
+
+#[tokio::test]
+async fn writes_out_the_queue_when_the_memory_budget_is_exhausted() {
+    let budget = MemoryBudget::new(10);
+    let mut writer = Builder::new().memory_budget(budget.clone()).new_write(Recorder::default());
+
+    writer.feed(&b"hello"[..]).await.unwrap();
+    writer.feed(&b"world"[..]).await.unwrap();
+    assert!(writer.get_ref().writes.is_empty());
+
+    // The second frame does not fit, it is queued outside of the budget
+    assert_eq!(budget.used(), 8);
+
+    // And written out along with the queue before the next frame
+    writer.feed(&b"!"[..]).await.unwrap();
+    assert_eq!(writer.get_ref().writes, vec![b"5:hello,5:world,".to_vec()]);
+    assert_eq!(budget.used(), 4);
+
+    drop(writer);
+    assert_eq!(budget.used(), 0);
+}
+
+#[tokio::test]
+async fn never_queues_past_the_memory_budget() {
+    let budget = MemoryBudget::new(16);
+    let mut writer = Builder::new().memory_budget(budget.clone()).new_write(Recorder::default());
+
+    writer.feed(&[b'x'; 100][..]).await.unwrap();
+    assert!(writer.get_ref().writes.is_empty());
+    assert_eq!(budget.used(), 0);
+
+    // The large frame is written out before the next one is accepted
+    writer.feed(&b"hi"[..]).await.unwrap();
+    assert_eq!(writer.get_ref().writes.concat().len(), 105);
+    assert_eq!(budget.used(), 5);
+
+    writer.flush().await.unwrap();
+    assert_eq!(budget.used(), 0);
+}
+
+#[tokio::test]
+async fn sends_bodies_from_readers() {
+    let mut writer = Builder::new().new_write(Recorder::default());
+
+    writer.feed(&b"a"[..]).await.unwrap();
+    writer.send_reader(11, &b"hello world"[..]).await.unwrap();
+    assert_eq!(writer.get_ref().writes.concat(), b"1:a,11:hello world,");
+
+    // The frame is still terminated, the writer can go on
+    match writer.send_reader(4, &b"hello"[..]).await {
+        Err(NetstringError::BodyTooLong { declared: 4 }) => {}
+        res => panic!("expected a body too long error, got {:?}", res),
+    }
+
+    writer.send(&b"b"[..]).await.unwrap();
+    assert_eq!(writer.get_ref().writes.concat(), b"1:a,11:hello world,4:hell,1:b,");
+
+    // The frame is left incomplete, the writer cannot go on
+    match writer.send_reader(6, &b"hello"[..]).await {
+        Err(NetstringError::BodyTooShort { declared: 6, read: 5 }) => {}
+        res => panic!("expected a body too short error, got {:?}", res),
+    }
+
+    assert!(writer.send(&b"c"[..]).await.is_err());
+    assert!(writer.send_reader(1, &b"c"[..]).await.is_err());
+    assert_eq!(writer.get_ref().writes.concat(), b"1:a,11:hello world,4:hell,1:b,6:hello");
+}
+
+#[tokio::test]
+async fn sends_bodies_from_streams() {
+    let chunks = || futures::stream::iter(vec![&b"hello"[..], b" ", b"world"]);
+    let mut writer = Builder::new().new_write::<_, &[u8]>(Recorder::default());
+
+    writer.send_stream(11, chunks()).await.unwrap();
+    assert_eq!(writer.get_ref().writes.concat(), b"11:hello world,");
+
+    // The frame is completed with what fits of the chunk going over
+    match writer.send_stream(8, chunks()).await {
+        Err(NetstringError::BodyTooLong { declared: 8 }) => {}
+        res => panic!("expected a body too long error, got {:?}", res),
+    }
+
+    writer.send(&b"b"[..]).await.unwrap();
+    assert_eq!(writer.get_ref().writes.concat(), b"11:hello world,8:hello wo,1:b,");
+
+    match writer.send_stream(12, chunks()).await {
+        Err(NetstringError::BodyTooShort { declared: 12, read: 11 }) => {}
+        res => panic!("expected a body too short error, got {:?}", res),
+    }
+
+    assert!(writer.send(&b"c"[..]).await.is_err());
+    assert!(writer.send_stream(1, chunks()).await.is_err());
+}
+
+#[tokio::test]
+async fn rejects_large_bodies() {
+    let mut writer = Builder::new().max_frame_length(4).new_write::<_, &[u8]>(Recorder::default());
+
+    match writer.send_reader(5, &b"hello"[..]).await {
+        Err(NetstringError::FrameTooLarge { declared: 5, max: 4 }) => {}
+        res => panic!("expected a frame too large error, got {:?}", res),
+    }
+
+    assert!(writer.get_ref().writes.is_empty());
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn sends_files() {
+    use std::io::{Seek, SeekFrom, Write};
+    use tokio::io::AsyncReadExt;
+    use tokio::net::{TcpListener, TcpStream};
+
+    let path = std::env::temp_dir().join(format!("tokio-netstring-send-file-{}", std::process::id()));
+    let mut file = std::fs::OpenOptions::new().read(true).write(true).create(true).truncate(true).open(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    file.write_all(b"skip:hello world").unwrap();
+    file.seek(SeekFrom::Start(5)).unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (socket, accepted) = tokio::join!(TcpStream::connect(addr), listener.accept());
+    let mut peer = accepted.unwrap().0;
+
+    let mut writer = Builder::new().new_write::<_, &[u8]>(socket.unwrap());
+
+    match writer.send_file(10, &file).await {
+        Err(NetstringError::BodyTooLong { declared: 10 }) => {}
+        res => panic!("expected a body too long error, got {:?}", res),
+    }
+
+    writer.feed(&b"a"[..]).await.unwrap();
+    writer.send_file(11, &file).await.unwrap();
+    drop(writer);
+
+    let mut received = Vec::new();
+    peer.read_to_end(&mut received).await.unwrap();
+    assert_eq!(received, b"1:a,11:hello world,");
+}
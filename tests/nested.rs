@@ -0,0 +1,83 @@
+use bytes::{Bytes, BytesMut};
+use tokio_netstring::{Builder, NestedReader, NestedWriter, NetstringError};
+
+#[test]
+fn writes_nested_frames() {
+    let mut writer = NestedWriter::new();
+    writer.push(&b"get"[..]);
+    writer.begin();
+    writer.push(&b"key"[..]).push(vec![b'x'; 12]);
+    writer.begin().end();
+    writer.end();
+    writer.push(Bytes::new());
+
+    let expected = b"3:get,25:3:key,12:xxxxxxxxxxxx,0:,,0:,";
+    assert_eq!(writer.encoded_len(), expected.len());
+
+    let mut vec = Vec::new();
+    writer.write_to(&mut vec);
+    assert_eq!(vec, expected);
+    assert_eq!(writer.finish(), &expected[..]);
+}
+
+#[test]
+#[should_panic(expected = "nested frame left open")]
+fn rejects_unclosed_frames() {
+    let mut writer = NestedWriter::new();
+    writer.begin().push(&b"a"[..]);
+    writer.finish();
+}
+
+#[test]
+fn reads_nested_frames() {
+    let frame = BytesMut::from(&b"3:get,25:3:key,12:xxxxxxxxxxxx,0:,,"[..]).freeze();
+    let mut fields = NestedReader::new(frame.clone());
+
+    assert_eq!(fields.read().unwrap(), "get");
+
+    let mut args = fields.read_nested().unwrap();
+    assert_eq!(args.read().unwrap(), "key");
+
+    // Payloads point into the original frame
+    let value = args.read().unwrap();
+    assert_eq!(value.as_ptr(), frame[18..].as_ptr());
+
+    assert!(args.read_nested().unwrap().is_empty());
+    assert!(args.is_empty());
+    assert!(fields.is_empty());
+
+    match fields.read() {
+        Err(NetstringError::UnexpectedEof { expected: None, buffered: 0 }) => {}
+        res => panic!("expected an unexpected eof error, got {:?}", res),
+    }
+}
+
+#[test]
+fn reports_malformed_nested_frames() {
+    let mut fields = NestedReader::new(Bytes::from_static(b"6:2:ab;x,"));
+    let mut args = fields.read_nested().unwrap();
+
+    match args.read() {
+        Err(NetstringError::MissingTerminator { found: b';', .. }) => {}
+        res => panic!("expected a missing terminator error, got {:?}", res),
+    }
+
+    assert!(args.next().is_none());
+}
+
+#[test]
+fn reads_nested_frames_with_the_builder_settings() {
+    let frame = Bytes::from_static(b"3:get,16:3:key,7:1234567,,");
+
+    // Nested frames are only bounded by the payload holding them
+    let mut fields = Builder::new().max_frame_length(2).length_field_offset(1).new_nested_reader(frame.clone());
+    assert_eq!(fields.read().unwrap(), "get");
+    assert_eq!(fields.read_nested().unwrap().nth(1).unwrap().unwrap(), "1234567");
+
+    // Other settings apply at every level
+    let frame = Bytes::from_static(b"6:02:ab,,");
+    assert!(NestedReader::new(frame.clone()).read_nested().unwrap().read().is_err());
+
+    let mut fields = Builder::new().lenient(true).new_nested_reader(frame);
+    assert_eq!(fields.read_nested().unwrap().read().unwrap(), "ab");
+}
@@ -0,0 +1,125 @@
+#![cfg(feature = "serde")]
+
+use bytes::BytesMut;
+use serde::{Deserialize, Serialize};
+use tokio_netstring::serde::{from_bytes, to_bytes, Error};
+use tokio_netstring::NetstringError;
+
+use std::collections::BTreeMap;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Op {
+    Ping,
+    Get(u64),
+    Move(i32, i32),
+    Rename { from: String, to: String },
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Request<'a> {
+    id: u32,
+    name: &'a str,
+    #[serde(with = "serde_bytes_slice")]
+    blob: &'a [u8],
+    ops: Vec<Op>,
+    tags: BTreeMap<String, bool>,
+    ttl: Option<f64>,
+    reply: Option<char>,
+    meta: (),
+}
+
+// Borrows the bytes as is rather than as a sequence of integers
+mod serde_bytes_slice {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(v: &[u8], s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_bytes(v)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<&'de [u8], D::Error> {
+        <&[u8]>::deserialize(d)
+    }
+}
+
+#[test]
+fn encodes_scalars_as_text() {
+    assert_eq!(to_bytes(&-12i8).unwrap(), &b"-12"[..]);
+    assert_eq!(to_bytes(&u128::MAX).unwrap(), u128::MAX.to_string().as_bytes());
+    assert_eq!(to_bytes(&1.5f32).unwrap(), &b"1.5"[..]);
+    assert_eq!(to_bytes(&false).unwrap(), &b"false"[..]);
+    assert_eq!(to_bytes("ü").unwrap(), "ü".as_bytes());
+
+    assert_eq!(from_bytes::<i64>(b"-12").unwrap(), -12);
+    assert_eq!(from_bytes::<u128>(u128::MAX.to_string().as_bytes()).unwrap(), u128::MAX);
+    assert_eq!(from_bytes::<f64>(b"1.5").unwrap(), 1.5);
+    assert!(from_bytes::<bool>(b"true").unwrap());
+    assert_eq!(from_bytes::<char>("ü".as_bytes()).unwrap(), 'ü');
+}
+
+#[test]
+fn encodes_compound_values_as_nested_frames() {
+    assert_eq!(to_bytes(&Some(Some(3))).unwrap(), &b"4:1:3,,"[..]);
+    assert_eq!(to_bytes(&Some(None::<u8>)).unwrap(), &b"0:,"[..]);
+    assert_eq!(to_bytes(&None::<u8>).unwrap(), &b""[..]);
+    assert_eq!(to_bytes(&vec![vec![1], vec![]]).unwrap(), &b"4:1:1,,0:,"[..]);
+    assert_eq!(to_bytes(&Op::Ping).unwrap(), &b"4:Ping,"[..]);
+    assert_eq!(to_bytes(&Op::Get(7)).unwrap(), &b"3:Get,1:7,"[..]);
+    assert_eq!(to_bytes(&Op::Move(1, -2)).unwrap(), &b"4:Move,9:1:1,2:-2,,"[..]);
+
+    assert_eq!(from_bytes::<Option<Option<u8>>>(b"4:1:3,,").unwrap(), Some(Some(3)));
+    assert_eq!(from_bytes::<Option<Option<u8>>>(b"0:,").unwrap(), Some(None));
+    assert_eq!(from_bytes::<Option<u8>>(b"").unwrap(), None);
+    assert_eq!(from_bytes::<Op>(b"4:Move,9:1:1,2:-2,,").unwrap(), Op::Move(1, -2));
+}
+
+#[test]
+fn round_trips_borrowing_from_frames() {
+    let request = Request {
+        id: 7,
+        name: "lookup",
+        blob: b"\xff\x00,:",
+        ops: vec![
+            Op::Ping,
+            Op::Get(u64::MAX),
+            Op::Move(-1, 1),
+            Op::Rename { from: "a".into(), to: "b".into() },
+        ],
+        tags: [("fast".to_string(), true), ("safe".to_string(), false)].into_iter().collect(),
+        ttl: Some(0.25),
+        reply: None,
+        meta: (),
+    };
+
+    // As yielded by a `FramedRead`
+    let frame: BytesMut = to_bytes(&request).unwrap();
+    let decoded: Request = from_bytes(&frame).unwrap();
+    assert_eq!(decoded, request);
+
+    // Strings and bytes point into the frame
+    let range = frame.as_ptr_range();
+    assert!(range.contains(&decoded.name.as_ptr()));
+    assert!(range.contains(&decoded.blob.as_ptr()));
+}
+
+#[test]
+fn reports_invalid_payloads() {
+    match from_bytes::<u8>(b"256") {
+        Err(Error::Invalid { expected: "a u8" }) => {}
+        res => panic!("expected an invalid value error, got {:?}", res),
+    }
+
+    match from_bytes::<(u8, u8)>(b"1:1,1:2,1:3,") {
+        Err(Error::TrailingFrames) => {}
+        res => panic!("expected a trailing frames error, got {:?}", res),
+    }
+
+    match from_bytes::<Vec<u8>>(b"1:1,1:2;") {
+        Err(Error::Netstring(NetstringError::MissingTerminator { found: b';', .. })) => {}
+        res => panic!("expected a missing terminator error, got {:?}", res),
+    }
+
+    match from_bytes::<(u8, u8)>(b"1:1,") {
+        Err(Error::Message(msg)) => assert!(msg.contains("invalid length"), "{}", msg),
+        res => panic!("expected a message, got {:?}", res),
+    }
+}
@@ -0,0 +1,134 @@
+#![cfg(feature = "std")]
+
+use tokio_netstring::sync::{Reader, Writer};
+use tokio_netstring::{Builder, NetstringError, ReserveStrategy};
+
+use std::io::{self, Read};
+
+// Hands out at most one byte per read call
+struct Trickle<'a>(&'a [u8]);
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.0.is_empty() || buf.is_empty() {
+            return Ok(0);
+        }
+
+        buf[0] = self.0[0];
+        self.0 = &self.0[1..];
+        Ok(1)
+    }
+}
+
+// Hands out at most `n` bytes per read call
+struct Chunked<'a>(&'a [u8], usize);
+
+impl Read for Chunked<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.0.len().min(buf.len()).min(self.1);
+
+        buf[..n].copy_from_slice(&self.0[..n]);
+        self.0 = &self.0[n..];
+        Ok(n)
+    }
+}
+
+#[test]
+fn reads_frames() {
+    let frames: Vec<_> = Reader::new(Trickle(b"5:hello,0:,5:world,")).collect::<io::Result<_>>().unwrap();
+    assert_eq!(frames, vec![b"hello".to_vec(), b"".to_vec(), b"world".to_vec()]);
+
+    let mut reader = Reader::new(&b"5:hello,"[..]);
+    let mut frame = b"stale".to_vec();
+
+    assert!(reader.read_frame_into(&mut frame).unwrap());
+    assert_eq!(frame, b"hello");
+    assert!(!reader.read_frame_into(&mut frame).unwrap());
+    assert_eq!(frame, b"hello");
+}
+
+#[test]
+fn stops_reading_at_the_first_error() {
+    let mut reader = Reader::new(&b"5:hello,5:world!5:again,"[..]);
+
+    assert_eq!(reader.next().unwrap().unwrap(), b"hello");
+
+    match NetstringError::from(reader.next().unwrap().unwrap_err()) {
+        NetstringError::MissingTerminator { found: b'!', .. } => {}
+        err => panic!("expected a missing terminator error, got {:?}", err),
+    }
+
+    assert!(reader.next().is_none());
+
+    let mut reader = Reader::new(&b"5:hel"[..]);
+    assert_eq!(reader.next().unwrap().unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    assert!(reader.next().is_none());
+}
+
+#[test]
+fn reads_with_the_builder_settings() {
+    let mut reader = Builder::new().max_frame_length(4).new_sync_read(&b"5:hello,"[..]);
+
+    match NetstringError::from(reader.next().unwrap().unwrap_err()) {
+        NetstringError::FrameTooLarge { declared: 5, max: 4 } => {}
+        err => panic!("expected a frame too large error, got {:?}", err),
+    }
+
+    let reader = Builder::new().resync(true).new_sync_read(&b"5:hello,junk5:world,"[..]);
+    let items: Vec<_> = reader.collect();
+
+    assert_eq!(items.len(), 3);
+    assert_eq!(items[0].as_ref().unwrap(), b"hello");
+    assert!(items[1].is_err());
+    assert_eq!(items[2].as_ref().unwrap(), b"world");
+}
+
+#[test]
+#[cfg(feature = "tokio")]
+fn ignores_the_memory_budget() {
+    use tokio_netstring::MemoryBudget;
+
+    let budget = MemoryBudget::new(4);
+    let mut reader = Builder::new().memory_budget(budget.clone()).new_sync_read(Trickle(b"5:hello,"));
+
+    assert_eq!(reader.next().unwrap().unwrap(), b"hello");
+    assert!(reader.next().is_none());
+    assert_eq!(budget.used(), 0);
+}
+
+#[test]
+fn writes_frames() {
+    let mut writer = Writer::new(Vec::new());
+
+    writer.write_frame(b"hello").unwrap();
+    writer.write_frame(b"").unwrap();
+    assert_eq!(writer.into_inner(), b"5:hello,0:,");
+
+    let mut writer = Builder::new()
+        .max_frame_length(4)
+        .length_field_offset(2)
+        .write_prefix(&b"v1"[..])
+        .new_sync_write(Vec::new());
+
+    writer.write_frame(b"abcd").unwrap();
+    assert!(writer.write_frame(b"hello").is_err());
+    assert_eq!(writer.get_ref(), b"v14:abcd,");
+}
+
+#[test]
+fn reads_large_frames_in_small_reads() {
+    let payload: Vec<u8> = (0..4 * 1024 * 1024).map(|i| i as u8).collect();
+    let mut writer = Writer::new(Vec::new());
+    writer.write_frame(&payload).unwrap();
+    writer.write_frame(b"next").unwrap();
+
+    // The whole frame is reserved up front, reads must not go over that
+    // capacity again and again
+    let mut reader = Builder::new()
+        .reserve_strategy(ReserveStrategy::Eager)
+        .new_sync_read(Chunked(writer.get_ref(), 5));
+
+    assert!(reader.next().unwrap().unwrap() == payload);
+    assert_eq!(reader.next().unwrap().unwrap(), b"next");
+    assert!(reader.next().is_none());
+}
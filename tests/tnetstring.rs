@@ -0,0 +1,282 @@
+use bytes::{Bytes, BytesMut};
+use tokio_netstring::tnetstring::{self, TnetstringDecoder, Value};
+use tokio_netstring::{Builder, NetstringError, OversizePolicy};
+
+fn dict(entries: &[(&'static str, Value)]) -> Value {
+    Value::Dict(entries.iter().map(|(key, value)| (Bytes::from_static(key.as_bytes()), value.clone())).collect())
+}
+
+#[test]
+fn encodes_and_decodes_every_type() {
+    let cases: &[(Value, &[u8])] = &[
+        (Value::from("hello"), b"5:hello,"),
+        (Value::from(""), b"0:,"),
+        (Value::Integer(-42), b"3:-42#"),
+        (Value::Float(1.5), b"3:1.5^"),
+        (Value::Float(-0.0), b"4:-0.0^"),
+        (Value::Bool(false), b"5:false!"),
+        (Value::Null, b"0:~"),
+        (Value::List(vec![]), b"0:]"),
+        (Value::List(vec![Value::Integer(1), Value::List(vec![Value::Null])]), b"10:1:1#3:0:~]]"),
+        (dict(&[("b", Value::Bool(true)), ("a", Value::from("x"))]), b"19:1:a,1:x,1:b,4:true!}"),
+        (
+            dict(&[("l", Value::List(vec![Value::List(vec![Value::Integer(1)]), Value::from("ab")]))]),
+            b"20:1:l,12:4:1:1#]2:ab,]}",
+        ),
+    ];
+
+    for &(ref value, encoded) in cases {
+        assert_eq!(value.encoded_len(), encoded.len());
+        assert_eq!(tnetstring::encode(value).unwrap(), encoded);
+
+        let (decoded, rest) = tnetstring::decode_one(encoded).unwrap();
+        assert_eq!(&decoded, value);
+        assert!(rest.is_empty());
+    }
+}
+
+#[test]
+fn rejects_invalid_values() {
+    let cases: &[(&[u8], u8)] = &[
+        (b"3:abc#", b'#'),
+        (b"3:1.x^", b'^'),
+        (b"3:yes!", b'!'),
+        (b"1:x~", b'~'),
+        (b"4:1:1#}", b'}'),
+        (b"4:1:a,}", b'}'),
+    ];
+
+    for &(encoded, expected) in cases {
+        match tnetstring::decode_one(encoded) {
+            Err(NetstringError::InvalidValue { tag }) => assert_eq!(tag, expected),
+            res => panic!("expected an invalid value error, got {:?}", res),
+        }
+    }
+
+    match tnetstring::decode_one(b"1:x?") {
+        Err(err @ NetstringError::MissingTerminator { found: b'?', .. }) => {
+            assert_eq!(err.to_string(), "missing terminator: expected one of `,#^!~}]`, found `?`");
+        }
+        res => panic!("expected a missing terminator error, got {:?}", res),
+    }
+
+    // Nested values must fit in the payload holding them
+    match tnetstring::decode_one(b"5:3:ab]]") {
+        Err(NetstringError::UnexpectedEof { expected: Some(1), buffered: 5 }) => {}
+        res => panic!("expected an unexpected eof error, got {:?}", res),
+    }
+}
+
+// `depth` lists nested in one another, along with their encoding
+#[test]
+fn encodes_floats_in_decimal() {
+    let cases = [1e300, -1e300, 1e-300, f64::MAX, f64::MIN_POSITIVE, 5e-324, 0.1, 123456789.0];
+
+    for n in cases {
+        let encoded = tnetstring::encode(&Value::Float(n)).unwrap();
+        assert!(encoded.iter().all(|&b| b"0123456789.-:^".contains(&b)), "{}", encoded.escape_ascii());
+
+        let (decoded, _) = tnetstring::decode_one(&encoded).unwrap();
+        assert_eq!(decoded, Value::Float(n));
+    }
+
+    assert_eq!(tnetstring::encode(&Value::Float(1e21)).unwrap(), b"24:1000000000000000000000.0^");
+
+    for n in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+        let value = Value::List(vec![Value::Float(n)]);
+
+        match tnetstring::encode(&value) {
+            Err(NetstringError::InvalidValue { tag: b'^' }) => {}
+            res => panic!("expected an invalid value error, got {:?}", res),
+        }
+
+        let mut dst = BytesMut::new();
+        assert!(value.encode_into(&mut dst).is_err());
+        assert!(dst.is_empty());
+    }
+}
+
+fn nested_lists(depth: usize) -> (Value, Vec<u8>) {
+    let mut value = Value::Null;
+    let mut encoded = b"0:~".to_vec();
+
+    for _ in 0..depth {
+        value = Value::List(vec![value]);
+        encoded = [format!("{}:", encoded.len()).into_bytes(), encoded, b"]".to_vec()].concat();
+    }
+
+    (value, encoded)
+}
+
+#[test]
+fn rejects_deeply_nested_values() {
+    let (value, encoded) = nested_lists(128);
+
+    assert_eq!(tnetstring::encode(&value).unwrap(), encoded);
+    assert_eq!(tnetstring::decode_one(&encoded).unwrap().0, value);
+
+    let (value, encoded) = nested_lists(129);
+    assert_eq!(value.encoded_len(), encoded.len());
+
+    match tnetstring::encode(&value) {
+        Err(NetstringError::NestingTooDeep { max: 128 }) => {}
+        res => panic!("expected a nesting too deep error, got {:?}", res),
+    }
+
+    let mut dst = BytesMut::new();
+    let res = Builder::new().new_tnetstring_encoder().encode_buf(&value, &mut dst);
+    assert!(matches!(res, Err(NetstringError::NestingTooDeep { max: 128 })));
+    assert!(dst.is_empty());
+
+    match tnetstring::decode_one(&encoded) {
+        Err(NetstringError::NestingTooDeep { max: 128 }) => {}
+        res => panic!("expected a nesting too deep error, got {:?}", res),
+    }
+}
+
+#[test]
+fn decodes_values_out_of_a_buffer() {
+    let mut decoder = Builder::new()
+        .length_field_offset(1)
+        .max_frame_length(16)
+        .new_tnetstring_decoder();
+
+    let mut src = BytesMut::from(&b"\x0112:5:hello,1:1#]\x012:4"[..]);
+
+    let value = decoder.decode_buf(&mut src).unwrap().unwrap();
+    assert_eq!(value, Value::List(vec![Value::from("hello"), Value::Integer(1)]));
+    assert!(decoder.decode_buf(&mut src).unwrap().is_none());
+
+    src.extend_from_slice(b"2#\x0120:");
+    assert_eq!(decoder.decode_buf(&mut src).unwrap(), Some(Value::Integer(42)));
+
+    match decoder.decode_buf(&mut src) {
+        Err(NetstringError::FrameTooLarge { declared: 20, max: 16 }) => {}
+        res => panic!("expected a frame too large error, got {:?}", res),
+    }
+
+    // Values are never handed over in chunks
+    let mut decoder = Builder::new()
+        .max_frame_length(4)
+        .on_oversize(OversizePolicy::Stream)
+        .new_tnetstring_decoder();
+
+    match decoder.decode_buf(&mut BytesMut::from(&b"5:hello,"[..])) {
+        Err(NetstringError::FrameTooLarge { declared: 5, max: 4 }) => {}
+        res => panic!("expected a frame too large error, got {:?}", res),
+    }
+
+    // The strings of a value share the memory of its frame
+    let mut src = BytesMut::from(&b"10:3:key,1:v,}"[..]);
+    let ptr = src.as_ptr();
+
+    match TnetstringDecoder::new().decode_buf_eof(&mut src).unwrap() {
+        Some(Value::Dict(dict)) => {
+            let (key, value) = dict.into_iter().next().unwrap();
+            assert_eq!(key, "key");
+            assert_eq!(value, Value::from("v"));
+            assert_eq!(key.as_ptr(), ptr.wrapping_add(5));
+        }
+        res => panic!("expected a dictionary, got {:?}", res),
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn frames_values() {
+    use futures::{SinkExt, TryStreamExt};
+    use tokio_util::codec::FramedWrite;
+
+    let mut io = Vec::new();
+    let values = vec![dict(&[("id", Value::Integer(7))]), Value::Float(0.25), Value::Null];
+
+    let mut sink = FramedWrite::new(&mut io, Builder::new().new_tnetstring_encoder());
+    for value in &values {
+        sink.send(value).await.unwrap();
+    }
+
+    assert_eq!(io, b"9:2:id,1:7#}4:0.25^0:~");
+
+    let decoded: Vec<Value> = Builder::new().new_tnetstring_read(&io[..]).try_collect().await.unwrap();
+    assert_eq!(decoded, values);
+
+    let mut stream = Builder::new().new_tnetstring_read(&io[..io.len() - 1]);
+    stream.try_next().await.unwrap();
+    stream.try_next().await.unwrap();
+
+    match stream.try_next().await {
+        Err(NetstringError::UnexpectedEof { expected: Some(1), buffered: 2 }) => {}
+        res => panic!("expected an unexpected eof error, got {:?}", res),
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn converts_serde_values() {
+    use serde::{Deserialize, Serialize};
+    use tokio_netstring::serde::Error;
+
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Shape {
+        Point,
+        Circle(f64),
+        Rect(u32, u32),
+        Named { name: String },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Scene {
+        id: u64,
+        shapes: Vec<Shape>,
+        tags: BTreeMap<String, i8>,
+        parent: Option<u64>,
+    }
+
+    let scene = Scene {
+        id: 3,
+        shapes: vec![
+            Shape::Point,
+            Shape::Circle(0.5),
+            Shape::Rect(1, 2),
+            Shape::Named { name: "n".into() },
+        ],
+        tags: [("z".to_string(), -1)].into_iter().collect(),
+        parent: None,
+    };
+
+    let value = tnetstring::to_value(&scene).unwrap();
+    assert_eq!(
+        value,
+        dict(&[
+            ("id", Value::Integer(3)),
+            ("shapes", Value::List(vec![
+                Value::from("Point"),
+                dict(&[("Circle", Value::Float(0.5))]),
+                dict(&[("Rect", Value::List(vec![Value::Integer(1), Value::Integer(2)]))]),
+                dict(&[("Named", dict(&[("name", Value::from("n"))]))]),
+            ])),
+            ("tags", dict(&[("z", Value::Integer(-1))])),
+            ("parent", Value::Null),
+        ])
+    );
+
+    let encoded = tnetstring::to_bytes(&scene).unwrap();
+    assert_eq!(encoded, tnetstring::encode(&value).unwrap());
+    assert_eq!(tnetstring::from_bytes::<Scene>(&encoded).unwrap(), scene);
+
+    // `Value` goes through other formats as well
+    let json = serde_json::to_string(&value).unwrap();
+    assert_eq!(serde_json::from_str::<Value>(&json).unwrap(), value);
+
+    match tnetstring::to_value(&u64::MAX) {
+        Err(Error::Invalid { .. }) => {}
+        res => panic!("expected an invalid value error, got {:?}", res),
+    }
+
+    match tnetstring::from_bytes::<u8>(b"1:1#0:~") {
+        Err(Error::TrailingFrames) => {}
+        res => panic!("expected a trailing frames error, got {:?}", res),
+    }
+}